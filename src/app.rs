@@ -1,8 +1,9 @@
 use core::time;
+use std::{collections::HashMap, thread};
 use base::WidgetBase;
 use egui::Color32;
 
-use crate::data::{Facade, tpvbc::BcastState, tpvbc::BcastStatus};
+use crate::data::{Facade, Source, tpvbc::BcastState, tpvbc::BcastStatus, tpvbc::Notification, tpvbc::NotificationKind, tpvbc::recorder::now_ms};
 
 mod base;
 mod tpv_focus;
@@ -21,10 +22,20 @@ mod ride_hight;
 mod ride_cadence;
 mod ride_time_in_zones;
 mod ride_history;
+mod ride_laps;
+mod ride_power_curve;
+mod ride_w_balance;
+mod ride_decoupling;
 mod setings_source;
+mod setings_athlete;
+mod replay_control;
+mod diagnostics;
 
 const APP_KEY: &str = "tpvui";
 
+// repaints on new data are at most this often
+const REPAINT_AFTER: time::Duration = time::Duration::from_millis(100);
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
@@ -45,7 +56,16 @@ pub struct TpvUiApp {
     widget_ride_cadence: ride_cadence::Widget,
     widget_ride_time_in_zones: ride_time_in_zones::Widget,
     widget_ride_history: ride_history::Widget,
+    widget_ride_laps: ride_laps::Widget,
+    widget_ride_power_curve: ride_power_curve::Widget,
+    widget_ride_w_balance: ride_w_balance::Widget,
+    widget_ride_decoupling: ride_decoupling::Widget,
     widget_settings_source: setings_source::Widget,
+    widget_settings_athlete: setings_athlete::Widget,
+    widget_replay_control: replay_control::Widget,
+    widget_diagnostics: diagnostics::Widget,
+    // name of the source shown by a widget, by title of the widget
+    widget_sources: HashMap<String, String>,
 
     #[serde(skip)]
     df: Facade,
@@ -70,7 +90,15 @@ impl Default for TpvUiApp {
             widget_ride_cadence: ride_cadence::Widget::new(),
             widget_ride_time_in_zones: ride_time_in_zones::Widget::new(),
             widget_ride_history: ride_history::Widget::new(),
+            widget_ride_laps: ride_laps::Widget::new(),
+            widget_ride_power_curve: ride_power_curve::Widget::new(),
+            widget_ride_w_balance: ride_w_balance::Widget::new(),
+            widget_ride_decoupling: ride_decoupling::Widget::new(),
             widget_settings_source: setings_source::Widget::new(),
+            widget_settings_athlete: setings_athlete::Widget::new(),
+            widget_replay_control: replay_control::Widget::new(),
+            widget_diagnostics: diagnostics::Widget::new(),
+            widget_sources: HashMap::new(),
             df: Facade::new(),
         }
     }
@@ -82,13 +110,52 @@ impl TpvUiApp {
         egui_extras::install_image_loaders(&cc.egui_ctx);
 
         // Load previous app state (if any).
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, APP_KEY).unwrap_or_default();
+        let mut app: TpvUiApp = match cc.storage {
+            Some(storage) => eframe::get_value(storage, APP_KEY).unwrap_or_default(),
+            None => Default::default(),
+        };
+
+        if let Some(path) = Self::credentials_path() {
+            app.widget_settings_source.load_credentials(&path);
         }
-        Default::default()
+
+        app.repaint_on_notifications(cc.egui_ctx.clone());
+        app
+    }
+
+    // credentials are not stored with the app state, which is plain RON
+    fn credentials_path() -> Option<std::path::PathBuf> {
+        eframe::storage_dir(APP_KEY).map(|d| d.join("credentials.json"))
+    }
+
+    // repaint soon after something changed instead of waiting for the next timed repaint
+    fn repaint_on_notifications(&self, ctx: egui::Context) {
+        // the frames would only be copied for nothing, what they change comes with the focus
+        let notifications = self.df.subscribe_to(&[
+            NotificationKind::Focus,
+            NotificationKind::Status,
+            NotificationKind::LapCompleted,
+            NotificationKind::RideReset,
+        ]);
+
+        thread::spawn(move || {
+            while let Ok(notification) = notifications.recv() {
+                // several streams and sources change at about the same time, one repaint is enough
+                for notification in std::iter::once(notification).chain(notifications.try_iter()) {
+                    match notification {
+                        Notification::Status { source, stream, status } => log::info!("Status of '{}' of source {} changed to {:?}", stream, source, status),
+                        Notification::LapCompleted { source, lap } => log::info!("Lap {} of source {} completed", lap, source),
+                        Notification::RideReset { source } => log::info!("Ride of source {} was reset", source),
+                        _ => (),
+                    }
+                }
+                ctx.request_repaint_after(REPAINT_AFTER);
+                thread::sleep(REPAINT_AFTER);
+            }
+        });
     }
     
-    fn data_source_status(&self, ui: &mut egui::Ui, ds: &BcastState, label: &str, ) {
+    fn data_source_status(&self, ui: &mut egui::Ui, ds: &BcastState, label: &str, stale_after: u64) {
         let mut status = String::from(label);
         let mut color = Color32::GREEN;
 
@@ -98,20 +165,102 @@ impl TpvUiApp {
         } else if ds.status == BcastStatus::NotOk {
                 status.push_str(" ⚠ ");
                 color = Color32::RED;
+        } else if ds.status == BcastStatus::ParseError {
+            status.push_str(" ✖ ");
+            color = Color32::ORANGE;
+        } else if ds.stale(stale_after) {
+            status.push_str(" ⌛ ");
+            color = Color32::YELLOW;
         } else {
             status.push_str(" ☭ ");
         }
 
         status.push_str(&format!("{:08}", ds.frame));
 
-        ui.label(
+        let response = ui.label(
             egui::RichText::new(status)
                 .color(color)
         );
 
+        if ds.status == BcastStatus::ParseError {
+            let error = ds.parse_error.clone().unwrap_or_default();
+            let mut body = ds.parse_error_body.clone().unwrap_or_default();
+
+            if body.chars().count() > 500 {
+                body = body.chars().take(500).collect::<String>() + " ...";
+            }
+
+            response.on_hover_text(format!("Failed to parse data: {}\n\n{}", error, body));
+        } else if ds.status == BcastStatus::NotOk {
+            if let Some(error) = &ds.last_error {
+                response.on_hover_text(format!("Failed to retrieve data: {}", error));
+            }
+        } else if ds.stale(stale_after) {
+            let age = |t: u64| format!("{:.1} s ago", now_ms().saturating_sub(t) as f32 / 1000.0);
+            let update = ds.last_update.map(age).unwrap_or(String::from("start"));
+
+            match ds.last_seen {
+                Some(seen) if ds.last_seen != ds.last_update => response.on_hover_text(
+                    format!("Connected, but no new data since {} (last response {})", update, age(seen))
+                ),
+                _ => response.on_hover_text(format!("No new data since {}", update)),
+            };
+        } else if let Some(schema) = &ds.schema {
+            response.on_hover_text(schema.describe());
+        }
+
         ui.add(egui::Separator::default().vertical());
     }
     
+    // one label per source if there are several, with the worst status of its streams
+    fn source_status(&self, ui: &mut egui::Ui, source: &Source) {
+        let streams = [
+            ("focus", source.tpv_focus_state()),
+            ("nearest", source.tpv_nearest_state()),
+            ("event", source.tpv_event_state()),
+            ("entries", source.tpv_entries_state()),
+            ("groups", source.tpv_groups_state()),
+            ("resultsIndv", source.tpv_results_indv_state()),
+            ("resultsTeam", source.tpv_results_team_state()),
+        ];
+        let stale_after = source.stale_after();
+
+        let severity = |ds: &BcastState| match ds.status {
+            BcastStatus::Unknown => 0,
+            BcastStatus::Ok if ds.stale(stale_after) => 2,
+            BcastStatus::Ok => 1,
+            BcastStatus::ParseError => 3,
+            BcastStatus::NotOk => 4,
+        };
+
+        let (symbol, color) = match streams.iter().map(|(_, ds)| severity(ds)).max().unwrap_or(0) {
+            0 => (" X ", Color32::GRAY),
+            1 => (" ☭ ", Color32::GREEN),
+            2 => (" ⌛ ", Color32::YELLOW),
+            3 => (" ✖ ", Color32::ORANGE),
+            _ => (" ⚠ ", Color32::RED),
+        };
+
+        let details: Vec<String> = streams.iter().map(|(name, ds)| {
+            let status = match ds.status {
+                BcastStatus::Unknown => String::from("stopped"),
+                BcastStatus::NotOk => format!("failed: {}", ds.last_error.as_ref().map(|e| e.to_string()).unwrap_or_default()),
+                BcastStatus::ParseError => format!("failed to parse: {}", ds.parse_error.clone().unwrap_or_default()),
+                BcastStatus::Ok if ds.stale(stale_after) => String::from("no new data"),
+                BcastStatus::Ok => String::from("receiving"),
+            };
+            format!("{}: {} ({} frames)", name, status, ds.frame)
+        }).collect();
+
+        ui.label(
+            egui::RichText::new(format!("{}{}{:08}", source.name(), symbol, streams[0].1.frame))
+                .color(color)
+        )
+        .on_hover_text(details.join("\n"));
+
+        ui.add(egui::Separator::default().vertical());
+    }
+
     fn menu_panel(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.with_layout(
@@ -128,15 +277,38 @@ impl TpvUiApp {
                         if ui.button("Settings").clicked() {
                             self.widget_settings_source.visible = !self.widget_settings_source.visible; 
                         }
+                        if ui.button("Replay").clicked() {
+                            self.widget_replay_control.visible = !self.widget_replay_control.visible;
+                        }
+                        if ui.button("Diagnostics").clicked() {
+                            self.widget_diagnostics.visible = !self.widget_diagnostics.visible;
+                        }
                         ui.separator();
                         if self.df.running() {
                             if ui.button("Stop receiving").clicked() {
                                 self.df.stop();
                             }                            
-                        } else {
-                            if ui.button("Start receiving").clicked() {
-                                self.df.start(self.widget_settings_source.url.clone());
-                            }    
+                        } else if ui.button("Start receiving").clicked() {
+                            self.df.set_config(self.widget_settings_source.bcast.clone());
+                            self.df.set_server_config(self.widget_settings_source.server.clone());
+                            self.df.set_push_config(self.widget_settings_source.push.clone());
+                            self.df.start(&self.widget_settings_source.all_sources());
+                        }
+                        ui.separator();
+                        if self.df.recording() {
+                            if ui.button("Stop recording").clicked() {
+                                self.df.stop_recording();
+                            }
+                        } else if ui.button("Start recording").clicked() {
+                            if let Err(e) = self.df.start_recording(&self.widget_settings_source.record_dir) {
+                                log::warn!("Failed to start recording: {}", e);
+                            }
+                        }
+                    });
+                    ui.add_space(16.0);
+                    ui.menu_button("Athlete", |ui| {
+                        if ui.button("Settings").clicked() {
+                            self.widget_settings_athlete.visible = !self.widget_settings_athlete.visible;
                         }
                     });
                     ui.add_space(16.0);                        
@@ -166,6 +338,10 @@ impl TpvUiApp {
                         self.widget_ride_cadence.show_label(ui);
                         self.widget_ride_time_in_zones.show_label(ui);
                         self.widget_ride_history.show_label(ui);
+                        self.widget_ride_laps.show_label(ui);
+                        self.widget_ride_power_curve.show_label(ui);
+                        self.widget_ride_w_balance.show_label(ui);
+                        self.widget_ride_decoupling.show_label(ui);
                     });
                     ui.collapsing(egui::RichText::new("TPV Raw").size(16.0), |ui| {     
                         self.widged_tpv_focus.show_label(ui);
@@ -207,49 +383,128 @@ impl TpvUiApp {
                     }
                     ui.add(egui::Separator::default().vertical());
 
-                    self.data_source_status(ui, &self.df.tpv_focus_state(), "focus");
-                    self.data_source_status(ui, &self.df.tpv_nearest_state(), "nearest");
-                    self.data_source_status(ui, &self.df.tpv_event_state(), "event");
-                    self.data_source_status(ui, &self.df.tpv_entries_state(), "entries");
-                    self.data_source_status(ui, &self.df.tpv_groups_state(), "groups");
-                    self.data_source_status(ui, &self.df.tpv_results_indv_state(), "resultsIndv");
-                    self.data_source_status(ui, &&self.df.tpv_results_team_state(), "resultsTeam");
+                    if let Some(path) = self.df.recording_path() {
+                        ui.label(
+                            egui::RichText::new("● REC")
+                                    .color(Color32::RED)
+                        )
+                        .on_hover_text(format!("Recording to {}", path.display()));
+                        ui.add(egui::Separator::default().vertical());
+                    }
+
+                    if let Some(addr) = self.df.serving() {
+                        ui.label(
+                            egui::RichText::new("⇄ Serving")
+                                    .color(Color32::GREEN)
+                        )
+                        .on_hover_text(format!("Serving bcast data on http://{}/bcast/", addr));
+                        ui.add(egui::Separator::default().vertical());
+                    } else if let Some(error) = self.df.server_error() {
+                        ui.label(
+                            egui::RichText::new("⇄ Serving")
+                                    .color(Color32::RED)
+                        )
+                        .on_hover_text(error);
+                        ui.add(egui::Separator::default().vertical());
+                    }
+
+                    if let Some(addr) = self.df.pushing() {
+                        ui.label(
+                            egui::RichText::new(format!("⇶ Push ({})", self.df.push_clients()))
+                                    .color(Color32::GREEN)
+                        )
+                        .on_hover_text(format!("Pushing events on http://{}/events to {} client(s)", addr, self.df.push_clients()));
+                        ui.add(egui::Separator::default().vertical());
+                    } else if let Some(error) = self.df.push_error() {
+                        ui.label(
+                            egui::RichText::new("⇶ Push")
+                                    .color(Color32::RED)
+                        )
+                        .on_hover_text(error);
+                        ui.add(egui::Separator::default().vertical());
+                    }
+
+                    if self.df.sources().len() > 1 {
+                        for source in self.df.sources() {
+                            self.source_status(ui, source);
+                        }
+                    } else {
+                        let source = &self.df.sources()[0];
+                        let stale_after = source.stale_after();
+
+                        self.data_source_status(ui, &source.tpv_focus_state(), "focus", stale_after);
+                        self.data_source_status(ui, &source.tpv_nearest_state(), "nearest", stale_after);
+                        self.data_source_status(ui, &source.tpv_event_state(), "event", stale_after);
+                        self.data_source_status(ui, &source.tpv_entries_state(), "entries", stale_after);
+                        self.data_source_status(ui, &source.tpv_groups_state(), "groups", stale_after);
+                        self.data_source_status(ui, &source.tpv_results_indv_state(), "resultsIndv", stale_after);
+                        self.data_source_status(ui, &source.tpv_results_team_state(), "resultsTeam", stale_after);
+                    }
                 }
             );
         });
     }
 
-    fn window_show_hide(ctx: &egui::Context, wdg: &impl WidgetBase, df: &Facade) {
+    fn window_show_hide(ctx: &egui::Context, wdg: &impl WidgetBase, df: &Facade, sources: &mut HashMap<String, String>) {
         if wdg.visible() {            
             egui::Window::new(wdg.title()).show(ctx, |ui| {
-                wdg.show_window(ui, df);
+                let selected = sources.entry(String::from(wdg.title())).or_default();
+
+                // nothing to choose from with only one source
+                if df.sources().len() > 1 {
+                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                        ui.label("Source:");
+                        egui::ComboBox::from_id_salt(wdg.title())
+                            .selected_text(df.source(selected).name())
+                            .show_ui(ui, |ui| {
+                                for source in df.sources() {
+                                    ui.selectable_value(selected, String::from(source.name()), source.name());
+                                }
+                            });
+                    });
+                    ui.separator();
+                }
+
+                wdg.show_window(ui, df.source(selected));
             });
         }          
     }
 
     fn widget_windows(&mut self, ctx: &egui::Context) {
-        TpvUiApp::window_show_hide(ctx, &self.widged_tpv_focus, &self.df); 
-        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_nearest, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_event, &self.df); 
-        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_entries, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_groups, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_results_indv, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_results_team, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_time, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_speed, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_heartrate, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_power, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_wind, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_hight, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_cadence, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_time_in_zones, &self.df);
-        TpvUiApp::window_show_hide(ctx, &self.widget_ride_history, &self.df);
+        TpvUiApp::window_show_hide(ctx, &self.widged_tpv_focus, &self.df, &mut self.widget_sources); 
+        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_nearest, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_event, &self.df, &mut self.widget_sources); 
+        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_entries, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_groups, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_results_indv, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_tpv_results_team, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_time, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_speed, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_heartrate, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_power, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_wind, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_hight, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_cadence, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_time_in_zones, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_history, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_laps, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_power_curve, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_w_balance, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_ride_decoupling, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_replay_control, &self.df, &mut self.widget_sources);
+        TpvUiApp::window_show_hide(ctx, &self.widget_diagnostics, &self.df, &mut self.widget_sources);
 
         if self.widget_settings_source.is_visible() {            
             egui::Window::new(self.widget_settings_source.get_title()).show(ctx, |ui| {
                 self.widget_settings_source.show_window(ui);
             });
         }
+
+        if self.widget_settings_athlete.is_visible() {
+            egui::Window::new(self.widget_settings_athlete.get_title()).show(ctx, |ui| {
+                self.widget_settings_athlete.show_window(ui);
+            });
+        }
     }
 }
 
@@ -257,12 +512,26 @@ impl eframe::App for TpvUiApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, APP_KEY, self);
+
+        if let Some(path) = Self::credentials_path() {
+            self.widget_settings_source.save_credentials(&path);
+        }
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.set_pixels_per_point(1.0);
-        ctx.request_repaint_after(time::Duration::from_millis(500));
+        // new data triggers a repaint by itself, this is for anything else (e.g. a session being recorded)
+        ctx.request_repaint_after(time::Duration::from_millis(1000));
+
+        self.df.set_auto_lap(self.widget_ride_laps.auto_lap.get());
+        self.df.set_athlete(self.widget_settings_athlete.athlete);
+
+        // lap hotkey, for the source the laps are shown of
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::L)) {
+            let name = self.widget_sources.get(self.widget_ride_laps.title()).cloned().unwrap_or_default();
+            self.df.source(&name).lap();
+        }
 
         self.menu_panel(ctx);
         self.widget_panel(ctx);
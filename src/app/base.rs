@@ -26,6 +26,14 @@ pub trait WidgetBase {
         });
     }  
 
+    // fields received from TPV which are not part of the model
+    fn extra_fields(&self, ui: &mut egui::Ui, extra: &crate::data::tpvbc::Extra) {
+        for (k, v) in extra.iter() {
+            self.key_value_simple(ui, k, v.to_string(), "");
+            ui.end_row();
+        }
+    }
+
     fn value_simple(&self, ui: &mut egui::Ui, v: String) {
         let fsize = self.default_text_size();
         ui.label(egui::RichText::new(v).size(fsize).color(egui::Color32::LIGHT_BLUE));
@@ -53,5 +61,5 @@ pub trait WidgetBase {
 
     fn visible(&self) -> bool;
 
-    fn show_window(&self, _ui: &mut egui::Ui, _df: &crate::data::Facade);
+    fn show_window(&self, _ui: &mut egui::Ui, _df: &crate::data::Source);
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+use crate::data::{Source, tpvbc::BcastState, tpvbc::BcastStatus, tpvbc::recorder::now_ms};
+use super::base::WidgetBase;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Widget {
+    pub visible: bool,
+}
+
+fn status(ds: &BcastState, stale_after: u64) -> &'static str {
+    match ds.status {
+        BcastStatus::Unknown => "stopped",
+        BcastStatus::NotOk => "failing",
+        BcastStatus::ParseError => "parse error",
+        BcastStatus::Ok if ds.stale(stale_after) => "no new data",
+        BcastStatus::Ok => "receiving",
+    }
+}
+
+impl WidgetBase for Widget {
+    fn title(&self) -> &'static str {
+        "Diagnostics"
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn show_label(&mut self, ui: &mut egui::Ui) {
+        self.visible = self.show_label_base(ui, self.visible);
+    }
+
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
+        let streams = [
+            ("focus", df.tpv_focus_state()),
+            ("nearest", df.tpv_nearest_state()),
+            ("event", df.tpv_event_state()),
+            ("entries", df.tpv_entries_state()),
+            ("groups", df.tpv_groups_state()),
+            ("resultsIndv", df.tpv_results_indv_state()),
+            ("resultsTeam", df.tpv_results_team_state()),
+        ];
+        let now = now_ms();
+        let stale_after = df.stale_after();
+
+        egui::Grid::new("diagnostics_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                for header in ["Stream", "Status", "Frames", "Rate", "Latency", "Last update", "Last response", "Errors", "Parse errors", "Last error"] {
+                    ui.label(egui::RichText::new(header).strong());
+                }
+                ui.end_row();
+
+                for (name, ds) in &streams {
+                    ui.label(*name);
+                    ui.label(status(ds, stale_after));
+                    ui.label(format!("{}", ds.frame));
+                    ui.label(format!("{:.1}/s", ds.rate));
+                    ui.label(ds.latency.map(|l| format!("{} ms", l)).unwrap_or(String::from("-")));
+                    ui.label(ds.last_update.map(|t| format!("{:.1} s ago", now.saturating_sub(t) as f32 / 1000.0)).unwrap_or(String::from("-")));
+                    ui.label(ds.last_seen.map(|t| format!("{:.1} s ago", now.saturating_sub(t) as f32 / 1000.0)).unwrap_or(String::from("-")));
+                    ui.label(format!("{}", ds.errors));
+                    ui.label(format!("{}", ds.parse_errors));
+                    ui.label(ds.last_error_text.clone().unwrap_or(String::from("-")));
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+
+        // x is the age of a sample in seconds, the newest samples are on the right
+        let seconds = |timestamp: u64| -(now.saturating_sub(timestamp) as f64 / 1000.0);
+
+        let rate_lines: Vec<Line> = streams.iter().map(|(name, ds)| {
+            let points: PlotPoints = ds.history.iter().map(|s| [seconds(s.timestamp), s.rate as f64]).collect();
+            Line::new(points).name(*name)
+        }).collect();
+
+        let latency_lines: Vec<Line> = streams.iter().map(|(name, ds)| {
+            let points: PlotPoints = ds.history.iter()
+                .filter_map(|s| s.latency.map(|l| [seconds(s.timestamp), l as f64]))
+                .collect();
+            Line::new(points).name(*name)
+        }).collect();
+
+        let error_lines: Vec<Line> = streams.iter().map(|(name, ds)| {
+            let points: PlotPoints = ds.history.iter().map(|s| [seconds(s.timestamp), s.errors as f64]).collect();
+            Line::new(points).name(*name)
+        }).collect();
+
+        let height = 120.0;
+
+        ui.label("Updates per second");
+        Plot::new("diagnostics_rate")
+            .legend(Legend::default())
+            .height(height)
+            .show(ui, |plot_ui| rate_lines.into_iter().for_each(|l| plot_ui.line(l)));
+
+        ui.label("Latency (ms)");
+        Plot::new("diagnostics_latency")
+            .legend(Legend::default())
+            .height(height)
+            .show(ui, |plot_ui| latency_lines.into_iter().for_each(|l| plot_ui.line(l)));
+
+        ui.label("Errors");
+        Plot::new("diagnostics_errors")
+            .legend(Legend::default())
+            .height(height)
+            .show(ui, |plot_ui| error_lines.into_iter().for_each(|l| plot_ui.line(l)));
+    }
+}
+
+impl Widget {
+    pub fn new() -> Widget {
+        Widget {
+            visible: false,
+        }
+    }
+}
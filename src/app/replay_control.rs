@@ -0,0 +1,86 @@
+use crate::data::{Source, tpvbc::replay};
+use super::base::WidgetBase;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Widget {
+    pub visible: bool,
+}
+
+fn hms(ms: u64) -> String {
+    let secs = ms / 1000;
+
+    format!("{:02}:{:02}:{:02}", secs / 60 / 60, (secs / 60) % 60, secs % 60)
+}
+
+impl WidgetBase for Widget {
+    fn title(&self) -> &'static str {
+        "Replay"
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn show_label(&mut self, ui: &mut egui::Ui) {
+        self.visible = self.show_label_base(ui, self.visible);
+    }
+
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
+        let state = match df.replay_state() {
+            Some(s) => s,
+            None => {
+                ui.label("No recorded session is replayed. To replay a session, set the data source URL to:\n");
+                ui.label(egui::RichText::new("\t- replay://<session_file>").italics());
+                return;
+            }
+        };
+
+        ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+            if state.paused {
+                if ui.button("▶ Play").clicked() {
+                    df.replay_set_paused(false);
+                }
+            } else if ui.button("⏸ Pause").clicked() {
+                df.replay_set_paused(true);
+            }
+
+            ui.label(format!("{} / {}", hms(state.position), hms(state.duration)));
+        });
+
+        ui.separator();
+
+        let mut position = state.position as f64 / 1000.0;
+        let duration = state.duration as f64 / 1000.0;
+
+        let response = ui.add(
+            egui::Slider::new(&mut position, 0.0..=duration)
+                .text("Position")
+                .suffix(" s")
+                .show_value(false)
+        );
+
+        // seeking rebuilds the ride from the start of the session, so only do it once the user let go
+        if response.drag_stopped() || (response.changed() && !response.dragged()) {
+            df.replay_seek((position * 1000.0) as u64);
+        }
+
+        let mut speed = state.speed;
+
+        if ui.add(
+            egui::Slider::new(&mut speed, replay::SPEED_MIN..=replay::SPEED_MAX)
+                .text("Speed")
+                .suffix("x")
+                .logarithmic(true)
+        ).changed() {
+            df.replay_set_speed(speed);
+        }
+    }
+}
+
+impl Widget {
+    pub fn new() -> Widget {
+        Widget {
+            visible: false,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::data::Facade;
+use crate::data::Source;
 use super::base::WidgetBase;
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -19,7 +19,7 @@ impl WidgetBase for Widget {
         self.visible = self.show_label_base(ui, self.visible);
     }
 
-    fn show_window(&self, ui: &mut egui::Ui, df: &Facade) {
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
         let ride = df.ride();
         
         // 2x2 grid
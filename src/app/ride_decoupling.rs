@@ -0,0 +1,99 @@
+use std::cell::Cell;
+
+use crate::data::Source;
+use super::base::WidgetBase;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Widget {
+    pub visible: bool,
+    // analyze the minutes `from` - `to` of the ride instead of the whole ride
+    #[serde(default)]
+    pub window: Cell<bool>,
+    #[serde(default)]
+    pub from: Cell<u32>,
+    #[serde(default)]
+    pub to: Cell<u32>,
+}
+
+impl WidgetBase for Widget {
+    fn title(&self) -> &'static str {
+        "Aerobic Decoupling"
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn show_label(&mut self, ui: &mut egui::Ui) {
+        self.visible = self.show_label_base(ui, self.visible);
+    }
+
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
+        let ride = df.ride();
+        let mut window = self.window.get();
+        let mut from = self.from.get();
+        let mut to = self.to.get();
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut window, "Window");
+            ui.add_enabled(window, egui::DragValue::new(&mut from).range(0..=to).suffix(" min"));
+            ui.label("-");
+            ui.add_enabled(window, egui::DragValue::new(&mut to).range(from..=1440).suffix(" min"));
+        });
+        self.window.set(window);
+        self.from.set(from);
+        self.to.set(to);
+
+        // the whole ride is kept up to date by the ride itself
+        let decoupling = if window {
+            ride.total.decoupling(from * 60, to * 60)
+        } else {
+            ride.decoupling
+        };
+
+        let Some(d) = decoupling else {
+            ui.label("Needs power and at least 30 s with heart rate in each half.");
+            return;
+        };
+
+        // below 5% the aerobic fitness is good for the effort
+        let color = |drift: f32| if drift < 5.0 { egui::Color32::LIGHT_GREEN } else { egui::Color32::YELLOW };
+
+        egui::Grid::new("decoupling_grid")
+        .num_columns(2)
+        .spacing([40.0, 4.0])
+        .show(ui, |ui| {
+            let fsize = self.default_text_size();
+
+            ui.label(egui::RichText::new("EF").size(fsize));
+            ui.label(egui::RichText::new(format!("{:1.2}", d.ef)).size(fsize).color(egui::Color32::LIGHT_BLUE));
+            ui.end_row();
+            ui.label(egui::RichText::new("Pw:HR").size(fsize));
+            ui.label(egui::RichText::new(format!("{:+2.1} %", d.pw_hr)).size(fsize).color(color(d.pw_hr)));
+            ui.end_row();
+            ui.label(egui::RichText::new("Pa:HR").size(fsize));
+            ui.label(egui::RichText::new(format!("{:+2.1} %", d.pa_hr)).size(fsize).color(color(d.pa_hr)));
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label(format!("EF first half {:.2}, second half {:.2} ({} - {})",
+            d.ef_first, d.ef_second, minutes(d.from), minutes(d.to)));
+    }
+}
+
+impl Widget {
+    pub fn new() -> Widget {
+        Widget {
+            visible: false,
+            window: Cell::new(false),
+            from: Cell::new(0),
+            to: Cell::new(60),
+        }
+    }
+}
+
+// 1:05 h
+fn minutes(secs: u32) -> String {
+    format!("{}:{:02} h", secs / 3600, (secs / 60) % 60)
+}
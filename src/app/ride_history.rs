@@ -1,11 +1,16 @@
+use std::cell::Cell;
+
 use egui_plot::{Legend, Line, Plot, PlotPoints};
 
-use crate::data::Facade;
+use crate::data::Source;
 use super::base::WidgetBase;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Widget {
     pub visible: bool,
+    // x-axis shows the time the samples were received instead of their number
+    #[serde(default)]
+    pub real_time: Cell<bool>,
 }
 
 impl WidgetBase for Widget {   
@@ -21,39 +26,38 @@ impl WidgetBase for Widget {
         self.visible = self.show_label_base(ui, self.visible);
     }
 
-    fn show_window(&self, ui: &mut egui::Ui, df: &Facade) {
-        let ride = df.ride();        
-
-        let hr_points: PlotPoints = (1..ride.total.hr.history.len()).map(|i| {
-            [
-                i as f64,
-                *ride.total.hr.history.get(i).unwrap() as f64,
-            ]
-        }).collect();
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
+        let ride = df.ride();
+        let real_time = self.real_time.get();
 
-        let hr_history = Line::new(hr_points).name("bpm");
+        let mut checked = real_time;
+        if ui.checkbox(&mut checked, "Real time (UTC)").changed() {
+            self.real_time.set(checked);
+        }
 
-        let pwr_points: PlotPoints = (1..ride.total.power.history.len()).map(|i| {
-            [
-                i as f64,
-                *ride.total.power.history.get(i).unwrap() as f64,
-            ]
-        }).collect();
+        let points = |history: &[u32]| -> PlotPoints {
+            (1..history.len().min(ride.total.timestamps.len())).map(|i| {
+                [
+                    if real_time { ride.total.timestamps[i].local as f64 / 1000.0 } else { i as f64 },
+                    history[i] as f64,
+                ]
+            }).collect()
+        };
 
-        let pwr_history = Line::new(pwr_points).name("W");
+        let hr_history = Line::new(points(&ride.total.hr.history)).name("bpm");
+        let pwr_history = Line::new(points(&ride.total.power.history)).name("W");
+        let cad_history = Line::new(points(&ride.total.cadence.history)).name("rpm");
 
-        let cad_points: PlotPoints = (1..ride.total.cadence.history.len()).map(|i| {
-            [
-                i as f64,
-                *ride.total.cadence.history.get(i).unwrap() as f64,
-            ]
-        }).collect();
+        // own ids, so the bounds of one axis are not applied to the other
+        let mut plot = Plot::new(if real_time { "History Graph (real time)" } else { "History Graph" }).legend(Legend::default());
 
-        let cad_history = Line::new(cad_points).name("rpm");
+        if real_time {
+            plot = plot
+                .x_axis_formatter(|mark, _| time_of_day(mark.value))
+                .label_formatter(|name, value| format!("{}\n{} {:.0}", time_of_day(value.x), name, value.y));
+        }
 
-        Plot::new("History Graph")
-        .legend(Legend::default())
-        .show(ui, |plot_ui| {
+        plot.show(ui, |plot_ui| {
             plot_ui.line(hr_history);
             plot_ui.line(pwr_history);
             plot_ui.line(cad_history);
@@ -65,6 +69,13 @@ impl Widget {
     pub fn new() -> Widget {
         Widget {
             visible: false,
+            real_time: Cell::new(false),
         }
     }
 }
+
+// hh:mm:ss (UTC) of seconds since epoch
+fn time_of_day(secs: f64) -> String {
+    let s = secs.rem_euclid(86400.0) as u32;
+    format!("{:02}:{:02}:{:02}", s / 3600, (s / 60) % 60, s % 60)
+}
@@ -0,0 +1,128 @@
+use std::cell::Cell;
+
+use crate::data::{athlete::Athlete, ride::{AutoLap, LapTrigger, Metrics, TimeInZones}, Source};
+use super::base::WidgetBase;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Widget {
+    pub visible: bool,
+    #[serde(default)]
+    pub auto_lap: Cell<AutoLap>,
+}
+
+impl WidgetBase for Widget {
+    fn title(&self) -> &'static str {
+        "Laps"
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn show_label(&mut self, ui: &mut egui::Ui) {
+        self.visible = self.show_label_base(ui, self.visible);
+    }
+
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
+        let ride = df.ride();
+        let mut auto_lap = self.auto_lap.get();
+
+        ui.horizontal(|ui| {
+            if ui.button("Lap").on_hover_text("Start a new split (L)").clicked() {
+                df.lap();
+            }
+            ui.separator();
+            ui.label("Auto lap every");
+            ui.add(egui::DragValue::new(&mut auto_lap.distance).range(0.0..=100.0).speed(0.1).suffix(" km"));
+            ui.label("or");
+            ui.add(egui::DragValue::new(&mut auto_lap.minutes).range(0..=120).suffix(" min"));
+            ui.label("(0 is off)");
+        });
+        self.auto_lap.set(auto_lap);
+
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::CollapsingHeader::new("Event laps").default_open(true).show(ui, |ui| {
+                Widget::table(ui, "event_laps_grid", &ride.laps(), &ride.athlete);
+            });
+            egui::CollapsingHeader::new("Splits").default_open(true).show(ui, |ui| {
+                Widget::table(ui, "splits_grid", &ride.splits(), &ride.athlete);
+            });
+        });
+    }
+}
+
+impl Widget {
+    pub fn new() -> Widget {
+        Widget {
+            visible: false,
+            auto_lap: Cell::new(AutoLap::default()),
+        }
+    }
+
+    fn table(ui: &mut egui::Ui, id: &str, laps: &[&Metrics], athlete: &Athlete) {
+        egui::Grid::new(id).striped(true).show(ui, |ui| {
+            for h in ["Lap", "Time", "Distance", "Speed", "Power", "NP", "IF", "TSS", "kJ", "HR", "Cadence", "Ascend", "HR Zone", "PWR Zone", "Ended by"] {
+                ui.label(egui::RichText::new(h).color(egui::Color32::DARK_GRAY));
+            }
+            ui.end_row();
+
+            for (i, lap) in laps.iter().enumerate() {
+                let current = i == laps.len() - 1;
+
+                Widget::lap_row(ui, lap, current, athlete);
+                ui.end_row();
+            }
+        });
+    }
+
+    fn lap_row(ui: &mut egui::Ui, lap: &Metrics, current: bool, athlete: &Athlete) {
+        let color = if current { egui::Color32::LIGHT_BLUE } else { egui::Color32::GRAY };
+        let value = |ui: &mut egui::Ui, v: String| {
+            ui.label(egui::RichText::new(v).color(color));
+        };
+
+        // TPV reports no laps in free rides
+        let number = if lap.lap > 0 { lap.lap.to_string() } else { String::from("-") };
+
+        value(ui, if current { format!("{} ▶", number) } else { number });
+        value(ui, lap.time_hms());
+        value(ui, format!("{:.2} km", lap.distance));
+        value(ui, format!("{:.1} / {:.1} km/h", lap.speed.avg, lap.speed.max));
+        value(ui, format!("{} / {} W", lap.power.avg, lap.power.max));
+        value(ui, format!("{} W", lap.load.np));
+        value(ui, format!("{:.2}", lap.load.intensity));
+        value(ui, format!("{:.0}", lap.load.tss));
+        value(ui, format!("{:.0}", lap.load.kj));
+        value(ui, format!("{} / {} bpm", lap.hr.avg, lap.hr.max));
+        value(ui, format!("{} rpm", lap.cadence.avg));
+        value(ui, format!("{} m", lap.height.ascend));
+
+        let hr_names: Vec<String> = athlete.hr_zones.zones.iter().map(|z| z.name.clone()).collect();
+        let pwr_names: Vec<String> = athlete.pwr_zones.zones.iter().map(|z| z.name.clone()).collect();
+
+        Widget::zones(ui, &lap.time_in_hr_zones, &hr_names, color);
+        Widget::zones(ui, &lap.time_in_pwr_zones, &pwr_names, color);
+
+        value(ui, String::from(match lap.ended_by {
+            Some(LapTrigger::Event) => "event",
+            Some(LapTrigger::Manual) => "lap button",
+            Some(LapTrigger::Distance) => "auto (km)",
+            Some(LapTrigger::Time) => "auto (min)",
+            None => "",
+        }));
+    }
+
+    // the zone most time was spent in, hover for all of them
+    fn zones(ui: &mut egui::Ui, tiz: &TimeInZones, names: &[String], color: egui::Color32) {
+        let name = |z: usize| names.get(z).cloned().unwrap_or(format!("Z{}", z + 1));
+
+        let dominant = tiz.zones.iter().enumerate().filter(|(_, t)| **t > 0).max_by_key(|(_, t)| **t).map(|(z, _)| z);
+
+        let details: Vec<String> = tiz.percentages().iter().zip(tiz.zones.iter()).enumerate()
+            .map(|(z, (p, t))| format!("{}: {:02}:{:02} ({:.0}%)", name(z), t / 60, t % 60, p))
+            .collect();
+
+        ui.label(egui::RichText::new(dominant.map(name).unwrap_or(String::from("-"))).color(color))
+            .on_hover_text(details.join("\n"));
+    }
+}
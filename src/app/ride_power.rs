@@ -1,4 +1,4 @@
-use crate::data::Facade;
+use crate::data::Source;
 use super::base::WidgetBase;
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -19,7 +19,7 @@ impl WidgetBase for Widget {
         self.visible = self.show_label_base(ui, self.visible);
     }
 
-    fn show_window(&self, ui: &mut egui::Ui, df: &Facade) {
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
         let ride = df.ride();
         
         // 2x2 grid
@@ -51,15 +51,19 @@ impl WidgetBase for Widget {
             ui.end_row();
         });
         
-        // 2x2 grid
+        // 1x1 grid, as sent by TPV and as computed from the samples
         egui::Grid::new("power_grid_1x1_b")
-        .min_col_width(210.0)
-        .max_col_width(210.0)
-        .min_row_height(100.0)
-        .num_columns(1)
+        .min_col_width(105.0)
+        .max_col_width(105.0)
+        .min_row_height(50.0)
+        .num_columns(2)
         .spacing([5.0, 5.0])
         .show(ui, |ui| {
             self.field_1x1(ui, String::from("nrm W"), format!("{:4.0}", ride.total.power.nrm));
+            self.field_1x1(ui, String::from("calc. nrm W"), format!("{:4.0}", ride.total.load.np));
+            ui.end_row();
+            self.field_1x1(ui, String::from("IF"), format!("{:1.2}", ride.total.load.intensity));
+            self.field_1x1(ui, String::from("avg W"), format!("{:4.0}", ride.total.power.avg));
             ui.end_row();
         });
 
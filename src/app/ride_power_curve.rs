@@ -0,0 +1,90 @@
+use egui_plot::{AxisHints, GridMark, HPlacement, Line, Plot, PlotPoints};
+
+use crate::data::Source;
+use super::base::WidgetBase;
+
+// durations (s) marked on the x-axis and listed above the plot
+const MARKS: [u32; 13] = [1, 5, 10, 30, 60, 120, 300, 600, 1200, 1800, 3600, 7200, 14400];
+const KEY_DURATIONS: [u32; 4] = [5, 60, 300, 1200];
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Widget {
+    pub visible: bool,
+}
+
+impl WidgetBase for Widget {
+    fn title(&self) -> &'static str {
+        "Power Curve"
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn show_label(&mut self, ui: &mut egui::Ui) {
+        self.visible = self.show_label_base(ui, self.visible);
+    }
+
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
+        let ride = df.ride();
+        let curve = ride.total.power_curve();
+        let weight = ride.athlete.weight as f64;
+
+        // best power for the key durations, if the ride is long enough
+        ui.horizontal(|ui| {
+            for d in KEY_DURATIONS {
+                if let Some(w) = ride.total.best_power(d) {
+                    ui.label(format!("{}: {:.0} W ({:.1} W/kg)", duration(d as f64), w, w as f64 / weight));
+                    ui.separator();
+                }
+            }
+        });
+
+        // the x-axis is logarithmic, seconds would squeeze everything but the long efforts
+        let points: PlotPoints = curve.iter().map(|(secs, w)| [(*secs as f64).ln(), *w as f64]).collect();
+        let line = Line::new(points).name("W").fill(0.0);
+
+        let y_axes = vec![
+            AxisHints::new_y().label("W"),
+            AxisHints::new_y()
+                .label("W/kg")
+                .formatter(move |mark: GridMark, _| format!("{:.1}", mark.value / weight))
+                .placement(HPlacement::Right),
+        ];
+
+        Plot::new("Power Curve")
+            .custom_y_axes(y_axes)
+            .x_grid_spacer(|_| MARKS.iter().map(|d| GridMark { value: (*d as f64).ln(), step_size: 1.0 }).collect())
+            .x_axis_formatter(|mark, _| duration(mark.value.exp()))
+            .label_formatter(move |_, value| format!("{}\n{:.0} W\n{:.1} W/kg", duration(value.x.exp()), value.y, value.y / weight))
+            .include_y(0.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .show(ui, |plot_ui| {
+                plot_ui.line(line);
+            });
+    }
+}
+
+impl Widget {
+    pub fn new() -> Widget {
+        Widget {
+            visible: false,
+        }
+    }
+}
+
+// 1 s, 5 min, 1 h 30 min
+fn duration(secs: f64) -> String {
+    let s = secs.round() as u32;
+
+    if s < 60 {
+        format!("{} s", s)
+    } else if s < 3600 {
+        format!("{} min", s / 60)
+    } else if s % 3600 == 0 {
+        format!("{} h", s / 3600)
+    } else {
+        format!("{} h {} min", s / 3600, (s % 3600) / 60)
+    }
+}
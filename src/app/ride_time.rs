@@ -1,4 +1,4 @@
-use crate::data::Facade;
+use crate::data::Source;
 use super::base::WidgetBase;
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -19,7 +19,7 @@ impl WidgetBase for Widget {
         self.visible = self.show_label_base(ui, self.visible);
     }
 
-    fn show_window(&self, ui: &mut egui::Ui, df: &Facade) {
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
         let ride = df.ride();
         
         // 2x2 grid
@@ -47,6 +47,9 @@ impl WidgetBase for Widget {
                 self.field_1x1(ui, String::from("★ TSS"), format!("{:3.0}", ride.total.tss));
                 self.field_1x1(ui, String::from("★ kcal"), format!("{:4.0}", ride.total.calories));
                 ui.end_row();
+                self.field_1x1(ui, String::from("calc. TSS"), format!("{:3.0}", ride.total.load.tss));
+                self.field_1x1(ui, String::from("kJ"), format!("{:4.0}", ride.total.load.kj));
+                ui.end_row();
             });
     }
 }
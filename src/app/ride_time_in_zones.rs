@@ -2,7 +2,7 @@ use std::ops::RangeInclusive;
 
 use egui_plot::{AxisHints, Bar, BarChart, GridMark, Legend, Plot};
 
-use crate::data::Facade;
+use crate::data::Source;
 use super::base::WidgetBase;
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -23,11 +23,11 @@ impl WidgetBase for Widget {
         self.visible = self.show_label_base(ui, self.visible);
     }
 
-    fn show_window(&self, ui: &mut egui::Ui, df: &Facade) {
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
         let ride = df.ride();        
         let w = 25.0;
-        let hr_percentages = ride.time_in_hr_zones.percentages();
-        let pwr_percentages = ride.time_in_pwr_zones.percentages(); 
+        let hr_percentages = ride.total.time_in_hr_zones.percentages();
+        let pwr_percentages = ride.total.time_in_pwr_zones.percentages(); 
 
         let zone_formatter = |mark: GridMark, _range: &RangeInclusive<f64>| -> String {
             let v = mark.value as i32;
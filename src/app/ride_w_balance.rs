@@ -0,0 +1,87 @@
+use std::cell::Cell;
+
+use egui_plot::{HLine, Line, Plot, PlotPoints};
+
+use crate::data::Source;
+use super::base::WidgetBase;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Widget {
+    pub visible: bool,
+    // warn when less W' (%) than this is left, 0 is off
+    #[serde(default)]
+    pub warn_below: Cell<u32>,
+}
+
+impl WidgetBase for Widget {
+    fn title(&self) -> &'static str {
+        "W' Balance"
+    }
+
+    fn visible(&self) -> bool {
+        self.visible
+    }
+
+    fn show_label(&mut self, ui: &mut egui::Ui) {
+        self.visible = self.show_label_base(ui, self.visible);
+    }
+
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
+        let ride = df.ride();
+        let w_bal = &ride.w_balance;
+        let mut warn_below = self.warn_below.get();
+        let warn = warn_below > 0 && w_bal.percent() < warn_below as f32;
+
+        egui::Grid::new("w_balance_grid_1x1")
+        .min_col_width(105.0)
+        .max_col_width(105.0)
+        .min_row_height(50.0)
+        .num_columns(2)
+        .spacing([5.0, 5.0])
+        .show(ui, |ui| {
+            self.field_1x1(ui, String::from("W' kJ"), format!("{:2.1}", w_bal.cur / 1000.0));
+            self.field_1x1(ui, String::from("W' %"), format!("{:3.0}", w_bal.percent()));
+            ui.end_row();
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Lowest {:.1} kJ, CP {} W, W' {:.1} kJ",
+                w_bal.min / 1000.0, ride.athlete.pwr_threshold, w_bal.capacity as f32 / 1000.0));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Warn below");
+            ui.add(egui::DragValue::new(&mut warn_below).range(0..=100).suffix(" %"));
+            ui.label("(0 is off)");
+        });
+        self.warn_below.set(warn_below);
+
+        if warn {
+            ui.label(egui::RichText::new("⚠ W' almost used up").size(21.0).color(egui::Color32::RED));
+        }
+
+        let points: PlotPoints = w_bal.history.iter().enumerate().skip(1)
+            .map(|(i, j)| [i as f64, *j as f64 / 1000.0])
+            .collect();
+
+        Plot::new("W' Balance")
+            .include_y(0.0)
+            .include_y(w_bal.capacity as f64 / 1000.0)
+            .label_formatter(|_, value| format!("{:.1} kJ", value.y))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(points).name("kJ").fill(0.0));
+                if warn_below > 0 {
+                    plot_ui.hline(HLine::new(w_bal.capacity as f64 * warn_below as f64 / 100_000.0).color(egui::Color32::RED));
+                }
+            });
+    }
+}
+
+impl Widget {
+    pub fn new() -> Widget {
+        Widget {
+            visible: false,
+            warn_below: Cell::new(0),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::data::athlete::AthleteConfig;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Widget {
+    pub visible: bool,
+    pub athlete: AthleteConfig,
+}
+
+impl Default for Widget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Widget {
+    pub fn new() -> Widget {
+        Widget {
+            visible: false,
+            athlete: AthleteConfig::new(),
+        }
+    }
+
+    pub fn get_title(&self) -> &'static str {
+        "Athlete Settings"
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn show_window(&mut self, ui: &mut egui::Ui) {
+        ui.label(egui::RichText::new("Zones, training load and W' balance are computed for this athlete:\n"));
+
+        egui::Grid::new("settings_athlete_grid").show(ui, |ui| {
+            ui.label("Weight:");
+            ui.add(egui::DragValue::new(&mut self.athlete.weight).range(30.0..=200.0).speed(0.1).suffix(" kg"));
+            ui.end_row();
+
+            ui.label("HR threshold:");
+            ui.add(egui::DragValue::new(&mut self.athlete.hr_threshold).range(80..=230).suffix(" bpm"));
+            ui.end_row();
+
+            ui.label("FTP / CP:");
+            ui.add(egui::DragValue::new(&mut self.athlete.pwr_threshold).range(50..=600).suffix(" W"))
+                .on_hover_text("Functional threshold power, also used as critical power for the W' balance");
+            ui.end_row();
+
+            // J in the model, kJ is what tests and other tools tell
+            let mut w_prime = self.athlete.w_prime as f32 / 1000.0;
+            ui.label("W':");
+            if ui.add(egui::DragValue::new(&mut w_prime).range(1.0..=60.0).speed(0.1).suffix(" kJ"))
+                .on_hover_text("Anaerobic work capacity above the critical power")
+                .changed() {
+                self.athlete.w_prime = (w_prime * 1000.0).round() as u32;
+            }
+            ui.end_row();
+        });
+
+        if ui.button("Reset to defaults").clicked() {
+            self.athlete = AthleteConfig::new();
+        }
+
+        ui.separator();
+        ui.label(egui::RichText::new("Changes apply to the current ride, the W' used up so far stays used up.").small());
+        ui.separator();
+
+        if ui.button("Close").clicked() {
+            self.visible = false;
+        }
+    }
+}
@@ -1,17 +1,162 @@
+use std::path::Path;
+
+use crate::data::{
+    SourceConfig,
+    tpvbc::{
+        credentials::{self, Auth, Credentials},
+        discovery::Discovery,
+        BcastConfig,
+        PushConfig,
+        ServerConfig,
+    },
+};
+
 #[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Widget {
     pub visible: bool,
+    pub name: String,
     pub url: String,
+    // of the source above, kept apart from the persisted app state like those of the other sources
+    #[serde(skip)]
+    pub credentials: Credentials,
+    // followed in addition to the one above
+    pub sources: Vec<SourceConfig>,
+    pub record_dir: String,
+    pub bcast: BcastConfig,
+    pub server: ServerConfig,
+    pub push: PushConfig,
+    #[serde(skip)]
+    discovery: Discovery,
+}
+
+impl Default for Widget {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Widget {
     pub fn new() -> Widget {
         Widget {
             visible: false,
+            name: String::from("TPV"),
             url: String::from("http://localhost:8080"),
+            credentials: Credentials::default(),
+            sources: Vec::new(),
+            record_dir: String::from("tpvui-sessions"),
+            bcast: BcastConfig::new(),
+            server: ServerConfig::new(),
+            push: PushConfig::new(),
+            discovery: Discovery::new(),
+        }
+    }
+
+    fn ms_value(ui: &mut egui::Ui, label: &str, value: &mut u64) {
+        ui.label(label);
+        ui.add(egui::DragValue::new(value).range(50..=120000).speed(10).suffix(" ms"));
+    }
+
+    /// All sources to follow, the primary one first. Sources without a name
+    /// are named after their position.
+    pub fn all_sources(&self) -> Vec<SourceConfig> {
+        let mut sources = vec![SourceConfig {
+            credentials: self.credentials.clone(),
+            ..SourceConfig::new(&self.name, &self.url)
+        }];
+        sources.extend(self.sources.iter().cloned());
+
+        for (i, source) in sources.iter_mut().enumerate() {
+            if source.name.trim().is_empty() {
+                source.name = format!("Source {}", i + 1);
+            }
+        }
+        sources
+    }
+
+    /// Take the credentials of the sources (by name) from `path`.
+    pub fn load_credentials(&mut self, path: &Path) {
+        let mut stored = match credentials::load(path) {
+            Ok(c) => c,
+            Err(e) => {
+                log::warn!("Failed to load credentials from {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        let names: Vec<String> = self.all_sources().into_iter().map(|s| s.name).collect();
+
+        self.credentials = stored.remove(&names[0]).unwrap_or_default();
+
+        for (source, name) in self.sources.iter_mut().zip(&names[1..]) {
+            source.credentials = stored.remove(name).unwrap_or_default();
         }
     }
 
+    /// Store the credentials of the sources (by name) in `path`.
+    pub fn save_credentials(&self, path: &Path) {
+        let stored = self.all_sources()
+            .into_iter()
+            .filter(|s| s.credentials != Credentials::default())
+            .map(|s| (s.name, s.credentials))
+            .collect();
+
+        if let Err(e) = credentials::save(path, &stored) {
+            log::warn!("Failed to save credentials to {}: {}", path.display(), e);
+        }
+    }
+
+    fn credentials_ui(ui: &mut egui::Ui, id: usize, credentials: &mut Credentials) {
+        egui::CollapsingHeader::new("Authentication and TLS").id_salt(("settings_source_auth", id)).show(ui, |ui| {
+            egui::Grid::new(("settings_source_auth_grid", id)).show(ui, |ui| {
+                let kind = match credentials.auth {
+                    Auth::None => "None",
+                    Auth::Basic { .. } => "Basic",
+                    Auth::Bearer { .. } => "Bearer token",
+                };
+
+                ui.label("Authentication:");
+                egui::ComboBox::from_id_salt(("settings_source_auth_kind", id))
+                    .selected_text(kind)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(kind == "None", "None").clicked() {
+                            credentials.auth = Auth::None;
+                        }
+                        if ui.selectable_label(kind == "Basic", "Basic").clicked() && kind != "Basic" {
+                            credentials.auth = Auth::Basic { user: String::new(), password: String::new() };
+                        }
+                        if ui.selectable_label(kind == "Bearer token", "Bearer token").clicked() && kind != "Bearer token" {
+                            credentials.auth = Auth::Bearer { token: String::new() };
+                        }
+                    });
+                ui.end_row();
+
+                match &mut credentials.auth {
+                    Auth::None => (),
+                    Auth::Basic { user, password } => {
+                        ui.label("User:");
+                        ui.text_edit_singleline(user);
+                        ui.end_row();
+
+                        ui.label("Password:");
+                        ui.add(egui::TextEdit::singleline(password).password(true));
+                        ui.end_row();
+                    }
+                    Auth::Bearer { token } => {
+                        ui.label("Token:");
+                        ui.add(egui::TextEdit::singleline(token).password(true));
+                        ui.end_row();
+                    }
+                }
+
+                ui.label("Trusted certificate:");
+                ui.text_edit_singleline(&mut credentials.trust)
+                    .on_hover_text("PEM file with a (self-signed) certificate to trust for https:// in addition to the well known CAs");
+                ui.end_row();
+            });
+        });
+    }
+
     pub fn get_title(&self) -> &'static str {
         "Data Source Settings"
     }
@@ -23,13 +168,150 @@ impl Widget {
     pub fn show_window(&mut self, ui: &mut egui::Ui) {
         ui.label(egui::RichText::new("Set base URL of TPV data source.Supported format:\n"));
         ui.label(egui::RichText::new("\t- http://<name_or_ip>:<port>").italics());
-        ui.label(egui::RichText::new("\t- file://<tpv_bcast_dir>\n").italics());
+        ui.label(egui::RichText::new("\t- https://<name_or_ip>:<port>").italics());
+        ui.label(egui::RichText::new("\t- file://<tpv_bcast_dir>").italics());
+        ui.label(egui::RichText::new("\t- replay://<session_file>").italics());
+        ui.label(egui::RichText::new("\t- sim://<seed> (built-in simulator, seed is optional)\n").italics());
         ui.separator();
 
         ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
+            ui.label("Name:");
+            ui.add(egui::TextEdit::singleline(&mut self.name).desired_width(80.0));
             ui.label("URL:");
             ui.text_edit_singleline(&mut self.url);    
         });
+        Self::credentials_ui(ui, 0, &mut self.credentials);
+
+        let mut remove = None;
+
+        for (i, source) in self.sources.iter_mut().enumerate() {
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
+                ui.label("Name:");
+                ui.add(egui::TextEdit::singleline(&mut source.name).desired_width(80.0));
+                ui.label("URL:");
+                ui.text_edit_singleline(&mut source.url);
+                if ui.button("✖").on_hover_text("Remove source").clicked() {
+                    remove = Some(i);
+                }
+            });
+            Self::credentials_ui(ui, i + 1, &mut source.credentials);
+        }
+
+        if let Some(i) = remove {
+            self.sources.remove(i);
+        }
+
+        ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
+            if ui.button("Add source").on_hover_text("Follow a further TPV instance, e.g. of another rider").clicked() {
+                self.sources.push(SourceConfig::default());
+            }
+            if ui.add_enabled(!self.discovery.searching(), egui::Button::new("Search network"))
+                .on_hover_text("Find TPVUI instances serving bcast data in the local network")
+                .clicked() {
+                self.discovery.search();
+            }
+            if self.discovery.searching() {
+                ui.spinner();
+            }
+        });
+
+        for found in self.discovery.found() {
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
+                ui.label(format!("{} ({})", found.name, found.url));
+                if ui.button("Use").on_hover_text("Set as URL of the first source").clicked() {
+                    self.url = found.url.clone();
+                }
+                if ui.button("Add").on_hover_text("Add as further source").clicked() {
+                    self.sources.push(SourceConfig::new(&found.name, &found.url));
+                }
+            });
+        }
+        ui.label(egui::RichText::new("Recording, serving and push are done for the first source.").small());
+
+        ui.separator();
+        ui.label(egui::RichText::new("Polling of an HTTP source (all times in ms):"));
+
+        egui::Grid::new("settings_source_poll_grid").show(ui, |ui| {
+            Self::ms_value(ui, "Focus:", &mut self.bcast.focus);
+            Self::ms_value(ui, "Nearest:", &mut self.bcast.nearest);
+            ui.end_row();
+
+            Self::ms_value(ui, "Event:", &mut self.bcast.event);
+            Self::ms_value(ui, "Entries:", &mut self.bcast.entries);
+            ui.end_row();
+
+            Self::ms_value(ui, "Groups:", &mut self.bcast.groups);
+            Self::ms_value(ui, "Results (individual):", &mut self.bcast.results_indv);
+            ui.end_row();
+
+            Self::ms_value(ui, "Results (team):", &mut self.bcast.results_team);
+            Self::ms_value(ui, "Timeout:", &mut self.bcast.timeout);
+            ui.end_row();
+
+            Self::ms_value(ui, "Backoff (first):", &mut self.bcast.backoff_min);
+            Self::ms_value(ui, "Backoff (max.):", &mut self.bcast.backoff_max);
+            ui.end_row();
+
+            ui.label("Jitter:");
+            ui.add(egui::DragValue::new(&mut self.bcast.jitter).range(0..=50).suffix(" %"));
+            Self::ms_value(ui, "Stale after:", &mut self.bcast.stale);
+            ui.end_row();
+        });
+
+        if ui.button("Reset to defaults").clicked() {
+            self.bcast = BcastConfig::new();
+        }
+
+        ui.separator();
+        ui.checkbox(&mut self.server.enabled, "Serve data read from file:// on /bcast/* to other TPVUI instances");
+
+        ui.add_enabled_ui(self.server.enabled, |ui| {
+            egui::Grid::new("settings_source_server_grid").show(ui, |ui| {
+                ui.label("Bind address:");
+                ui.text_edit_singleline(&mut self.server.bind);
+                ui.end_row();
+
+                ui.label("Port:");
+                ui.add(egui::DragValue::new(&mut self.server.port).range(1..=65535));
+                ui.end_row();
+
+                ui.label("Allowed clients:");
+                ui.text_edit_singleline(&mut self.server.allow)
+                    .on_hover_text("Comma separated addresses (192.168.1.10) or networks (192.168.1.0/24), empty to allow everyone");
+                ui.end_row();
+
+                ui.label("Announce:");
+                ui.checkbox(&mut self.server.announce, "Let other TPVUI instances find this one with _Search network_");
+                ui.end_row();
+
+                ui.label("Announced name:");
+                ui.add_enabled(self.server.announce, egui::TextEdit::singleline(&mut self.server.name).hint_text("host name"));
+                ui.end_row();
+            });
+        });
+
+        ui.separator();
+        ui.checkbox(&mut self.push.enabled, "Push ride metrics and frames to clients (server-sent events on /events)");
+
+        ui.add_enabled_ui(self.push.enabled, |ui| {
+            egui::Grid::new("settings_source_push_grid").show(ui, |ui| {
+                ui.label("Bind address:");
+                ui.text_edit_singleline(&mut self.push.bind);
+                ui.end_row();
+
+                ui.label("Port:");
+                ui.add(egui::DragValue::new(&mut self.push.port).range(1..=65535));
+                ui.end_row();
+            });
+        });
+
+        ui.separator();
+        ui.label(egui::RichText::new("Directory in which recorded sessions are stored:"));
+
+        ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
+            ui.label("Recordings:");
+            ui.text_edit_singleline(&mut self.record_dir);
+        });
 
         ui.separator();
         ui.label(egui::RichText::new("To enable settings, you must Stop/Start receiving!").color(ui.visuals().warn_fg_color));
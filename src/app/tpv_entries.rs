@@ -1,4 +1,4 @@
-use crate::data::Facade;
+use crate::data::Source;
 use super::base::WidgetBase;
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -19,7 +19,7 @@ impl WidgetBase for Widget {
         self.visible = self.show_label_base(ui, self.visible);
     }
 
-    fn show_window(&self, ui: &mut egui::Ui, df: &Facade) {
+    fn show_window(&self, ui: &mut egui::Ui, df: &Source) {
         let entries = df.tpv_entries_data();
         
         egui::ScrollArea::horizontal().show(ui, |ui| {
@@ -34,6 +34,8 @@ impl WidgetBase for Widget {
                         self.key_value_simple(ui, "Team Code", format!("{}", e.teamCode), "");
                         ui.end_row();
                         
+                        self.extra_fields(ui, &e.extra);
+
                         ui.label("");
                         ui.end_row();
                     }
@@ -1,66 +1,197 @@
-use tpvbc::interface::BcastStreamIf;
+use std::{io, path::PathBuf, sync::{mpsc, Arc}};
+
+use athlete::{Athlete, AthleteConfig};
+use ride::AutoLap;
+use tpvbc::tap::Tap;
+pub use tpvbc::{BcastConfig, BcastStreamIf, Credentials, Notification, NotificationKind, PushConfig, ServerConfig};
 
 pub mod ride;
 pub mod tpvbc;
 pub mod athlete;
 
+/// How bcast data is read, given by the URL of a source.
 #[derive(Clone, PartialEq)]
-pub enum BcastMethod {
+pub(crate) enum BcastMethod {
     HttpClient,
     File,
+    Replay,
+    Simulator,
 }
 
-pub struct Facade {
-    bcast_emthod: BcastMethod,
-    tpv: Box<dyn BcastStreamIf>,
+/// A named source of TPV bcast data, the URL tells how it is read.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SourceConfig {
+    pub name: String,
+    pub url: String,
+    // kept apart from the persisted app state
+    #[serde(skip)]
+    pub credentials: Credentials,
 }
 
-impl Facade {
-    pub fn new() -> Facade {
-        Facade {
-            bcast_emthod: BcastMethod::HttpClient,
-            tpv: Box::new(tpvbc::httpclient::BcastStream::new()),
+impl SourceConfig {
+    pub fn new(name: &str, url: &str) -> SourceConfig {
+        SourceConfig {
+            name: String::from(name),
+            url: String::from(url),
+            credentials: Credentials::default(),
         }
     }
+}
 
-    pub fn start(&mut self, url: String) {
-        if self.running() {
-            log::warn!("Steaming is already running! Stop first!");
-            return;
+impl Default for SourceConfig {
+    fn default() -> Self {
+        SourceConfig::new("", "http://localhost:8080")
+    }
+}
+
+fn bcast_method(url: &str) -> BcastMethod {
+    if url.starts_with("file://") {
+        BcastMethod::File
+    } else if url.starts_with("replay://") {
+        BcastMethod::Replay
+    } else if url.starts_with("sim://") {
+        BcastMethod::Simulator
+    } else {
+        BcastMethod::HttpClient
+    }
+}
+
+// the stream for a method, none if its cargo feature is disabled
+fn bcast_stream(method: &BcastMethod, tap: &Tap, config: &BcastConfig, credentials: &Credentials) -> Option<Box<dyn BcastStreamIf>> {
+    match method {
+        #[cfg(feature = "http-source")]
+        BcastMethod::HttpClient => Some(Box::new(tpvbc::httpclient::BcastStream::new(tap.clone(), config.clone(), credentials.clone()))),
+        #[cfg(feature = "fs-source")]
+        BcastMethod::File => Some(Box::new(tpvbc::filesystem::BcastStream::new(tap.clone()))),
+        BcastMethod::Replay => Some(Box::new(tpvbc::replay::BcastStream::new(tap.clone()))),
+        BcastMethod::Simulator => Some(Box::new(tpvbc::simulator::BcastStream::new(tap.clone()))),
+        #[allow(unreachable_patterns)]
+        _ => {
+            // only used by the disabled streams
+            let _ = (config, credentials);
+            None
         }
+    }
+}
 
-        let bcast_method;
+/// One TPV instance followed by the `Facade`, with its own ride and status.
+pub struct Source {
+    name: String,
+    bcast_emthod: BcastMethod,
+    tpv: Box<dyn BcastStreamIf>,
+    tap: Tap,
+    config: BcastConfig,
+    credentials: Credentials,
+    auto_lap: AutoLap,
+    athlete: AthleteConfig,
+}
+
+impl Source {
+    fn new(name: &str, tap: Tap, config: &BcastConfig) -> Source {
+        // an idle replay is always available if HTTP is not
+        let bcast_emthod = if cfg!(feature = "http-source") { BcastMethod::HttpClient } else { BcastMethod::Replay };
+        let tpv = bcast_stream(&bcast_emthod, &tap, config, &Credentials::default())
+            .unwrap_or_else(|| Box::new(tpvbc::replay::BcastStream::new(tap.clone())));
 
-        if url.starts_with("file://") {
-            bcast_method = BcastMethod::File;
-        } else {
-            bcast_method = BcastMethod::HttpClient;
+        Source {
+            name: String::from(name),
+            bcast_emthod,
+            tpv,
+            tap,
+            config: config.clone(),
+            credentials: Credentials::default(),
+            auto_lap: AutoLap::default(),
+            athlete: AthleteConfig::default(),
         }
+    }
+
+    fn start(&mut self, url: String, credentials: &Credentials) {
+        let bcast_method = bcast_method(&url);
 
-        if self.bcast_emthod != bcast_method {
-            if bcast_method == BcastMethod::File {
-                log::info!("TPV bcast data is read from FS");
-                self.tpv = Box::new(tpvbc::filesystem::BcastStream::new());
-            } else {
-                log::info!("TPV bcast data is read from HTTP (client)");
-                self.tpv = Box::new(tpvbc::httpclient::BcastStream::new());
+        // the HTTP workers get their credentials on creation
+        let recreate = self.bcast_emthod != bcast_method
+            || (bcast_method == BcastMethod::HttpClient && self.credentials != *credentials);
+
+        self.credentials = credentials.clone();
+
+        if recreate {
+            match bcast_stream(&bcast_method, &self.tap, &self.config, &self.credentials) {
+                Some(tpv) => {
+                    match bcast_method {
+                        BcastMethod::File => log::info!("TPV bcast data for '{}' is read from FS", self.name),
+                        BcastMethod::Replay => log::info!("TPV bcast data for '{}' is replayed from a recorded session", self.name),
+                        BcastMethod::Simulator => log::info!("TPV bcast data for '{}' is generated by the simulator", self.name),
+                        BcastMethod::HttpClient => log::info!("TPV bcast data for '{}' is read from HTTP (client)", self.name),
+                    }
+                    self.tpv = tpv;
+                    self.bcast_emthod = bcast_method;
+                }
+                None => {
+                    log::warn!("Can not read '{}' for '{}', support was not built in (see the cargo features)", url, self.name);
+                    return;
+                }
             }
-            self.bcast_emthod = bcast_method;
         }
 
-        log::info!("Facade::start");
+        // a new stream has a new ride
+        self.tpv.ride_set_auto_lap(self.auto_lap);
+        self.tpv.ride_set_athlete(Athlete::from_config(&self.athlete));
         self.tpv.start(url);
     }
 
-    pub fn stop(&self) {
-        log::info!("Facade::stop");
-        self.tpv.stop();
+    fn set_config(&mut self, config: &BcastConfig) {
+        self.config = config.clone();
+
+        // the HTTP workers get their config on creation
+        if self.bcast_emthod == BcastMethod::HttpClient && !self.running() {
+            if let Some(tpv) = bcast_stream(&self.bcast_emthod, &self.tap, &self.config, &self.credentials) {
+                self.tpv = tpv;
+                self.tpv.ride_set_auto_lap(self.auto_lap);
+                self.tpv.ride_set_athlete(Athlete::from_config(&self.athlete));
+            }
+        }
+    }
+
+    fn set_auto_lap(&mut self, auto_lap: AutoLap) {
+        self.auto_lap = auto_lap;
+        self.tpv.ride_set_auto_lap(auto_lap);
+    }
+
+    fn set_athlete(&mut self, athlete: AthleteConfig) {
+        self.athlete = athlete;
+        self.tpv.ride_set_athlete(Athlete::from_config(&athlete));
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
     pub fn running(&self) -> bool {
         self.tpv.running()
     }
 
+    /// Time in ms after which a running stream without new data is stale.
+    pub fn stale_after(&self) -> u64 {
+        self.config.stale
+    }
+
+    pub fn replay_state(&self) -> Option<tpvbc::ReplayState> {
+        self.tpv.replay_state()
+    }
+
+    pub fn replay_set_paused(&self, paused: bool) {
+        self.tpv.replay_set_paused(paused);
+    }
+
+    pub fn replay_set_speed(&self, speed: f32) {
+        self.tpv.replay_set_speed(speed);
+    }
+
+    pub fn replay_seek(&self, position: u64) {
+        self.tpv.replay_seek(position);
+    }
+
     pub fn tpv_focus_data(&self) -> tpvbc::Focus {
         self.tpv.focus_data()
     }
@@ -117,7 +248,248 @@ impl Facade {
         self.tpv.results_team_state()
     }
 
-    pub fn ride(&self) -> ride::Ride {
+    pub fn ride(&self) -> Arc<ride::Ride> {
         self.tpv.ride()
     }
+
+    /// End the current split of the ride and start a new one.
+    pub fn lap(&self) {
+        self.tpv.ride_lap();
+    }
+}
+
+/// Follows one or more sources. Recording, serving and pushing are done for
+/// the first (primary) source, notifications are sent for all of them.
+pub struct Facade {
+    sources: Vec<Source>,
+    tap: Tap,
+    config: BcastConfig,
+    auto_lap: AutoLap,
+    athlete: AthleteConfig,
+    server_config: ServerConfig,
+    server_error: Option<String>,
+    push_config: PushConfig,
+    push_error: Option<String>,
+}
+
+impl Facade {
+    pub fn new() -> Facade {
+        let tap = Tap::new();
+        let config = BcastConfig::new();
+
+        Facade {
+            sources: vec![Source::new("", tap.clone(), &config)],
+            tap,
+            config,
+            auto_lap: AutoLap::default(),
+            athlete: AthleteConfig::default(),
+            server_config: ServerConfig::new(),
+            server_error: None,
+            push_config: PushConfig::new(),
+            push_error: None,
+        }
+    }
+
+    /// Start receiving from all given sources, the first one is the primary.
+    pub fn start(&mut self, sources: &[SourceConfig]) {
+        if self.running() {
+            log::warn!("Steaming is already running! Stop first!");
+            return;
+        }
+
+        if sources.is_empty() {
+            log::warn!("No source to start!");
+            return;
+        }
+
+        // sources are kept as long as they are configured, so a stream is only
+        // recreated if its method changes
+        self.sources.truncate(sources.len());
+
+        for (i, sc) in sources.iter().enumerate() {
+            match self.sources.get_mut(i) {
+                Some(source) => source.name = sc.name.clone(),
+                None => {
+                    let tap = Tap::with_events(self.tap.events.fork(i));
+                    let mut source = Source::new(&sc.name, tap, &self.config);
+                    source.set_auto_lap(self.auto_lap);
+                    source.set_athlete(self.athlete);
+                    self.sources.push(source);
+                }
+            }
+        }
+
+        log::info!("Facade::start");
+
+        for (source, sc) in self.sources.iter_mut().zip(sources) {
+            source.start(sc.url.clone(), &sc.credentials);
+        }
+
+        self.start_serving();
+    }
+
+    #[cfg(feature = "serve")]
+    fn start_serving(&mut self) {
+        // only data read from the FS is served, everything else is already available via HTTP
+        self.server_error = None;
+
+        if self.sources[0].bcast_emthod == BcastMethod::File && self.server_config.enabled {
+            if let Err(e) = self.tap.server.start(&self.server_config) {
+                log::warn!("{}", e);
+                self.server_error = Some(e);
+            }
+        }
+
+        self.push_error = None;
+
+        if self.push_config.enabled {
+            if let Err(e) = self.tap.push.start(&self.push_config) {
+                log::warn!("{}", e);
+                self.push_error = Some(e);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "serve"))]
+    fn start_serving(&mut self) {
+        let not_built = || String::from("Serving and pushing support was not built in (see the cargo features)");
+
+        self.server_error = self.server_config.enabled.then(not_built);
+        self.push_error = self.push_config.enabled.then(not_built);
+    }
+
+    pub fn set_config(&mut self, config: BcastConfig) {
+        if self.config == config {
+            return;
+        }
+
+        self.config = config;
+
+        for source in self.sources.iter_mut() {
+            source.set_config(&self.config);
+        }
+    }
+
+    /// Auto-lap rules for the splits of all sources.
+    pub fn set_auto_lap(&mut self, auto_lap: AutoLap) {
+        if self.auto_lap == auto_lap {
+            return;
+        }
+
+        self.auto_lap = auto_lap;
+
+        for source in self.sources.iter_mut() {
+            source.set_auto_lap(auto_lap);
+        }
+    }
+
+    /// The athlete the rides of all sources are analyzed for.
+    pub fn set_athlete(&mut self, athlete: AthleteConfig) {
+        if self.athlete == athlete {
+            return;
+        }
+
+        self.athlete = athlete;
+
+        for source in self.sources.iter_mut() {
+            source.set_athlete(athlete);
+        }
+    }
+
+    pub fn set_server_config(&mut self, config: ServerConfig) {
+        self.server_config = config;
+    }
+
+    pub fn set_push_config(&mut self, config: PushConfig) {
+        self.push_config = config;
+    }
+
+    pub fn stop(&self) {
+        log::info!("Facade::stop");
+        for source in self.sources.iter() {
+            source.tpv.stop();
+        }
+        #[cfg(feature = "serve")]
+        {
+            self.tap.server.stop();
+            self.tap.push.stop();
+        }
+    }
+
+    pub fn running(&self) -> bool {
+        self.sources.iter().any(|s| s.running())
+    }
+
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    /// The source with the given name, the primary one if there is none.
+    pub fn source(&self, name: &str) -> &Source {
+        self.sources.iter().find(|s| s.name == name).unwrap_or(&self.sources[0])
+    }
+
+    pub fn start_recording(&self, dir: &str) -> io::Result<PathBuf> {
+        log::info!("Facade::start_recording");
+        self.tap.recorder.start(dir)
+    }
+
+    pub fn stop_recording(&self) {
+        log::info!("Facade::stop_recording");
+        self.tap.recorder.stop();
+    }
+
+    pub fn recording(&self) -> bool {
+        self.tap.recorder.recording()
+    }
+
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        self.tap.recorder.path()
+    }
+
+    pub fn serving(&self) -> Option<String> {
+        #[cfg(feature = "serve")]
+        return self.tap.server.serving();
+        #[cfg(not(feature = "serve"))]
+        None
+    }
+
+    pub fn server_error(&self) -> Option<String> {
+        self.server_error.clone()
+    }
+
+    pub fn pushing(&self) -> Option<String> {
+        #[cfg(feature = "serve")]
+        return self.tap.push.serving();
+        #[cfg(not(feature = "serve"))]
+        None
+    }
+
+    pub fn push_clients(&self) -> usize {
+        #[cfg(feature = "serve")]
+        return self.tap.push.clients();
+        #[cfg(not(feature = "serve"))]
+        0
+    }
+
+    pub fn push_error(&self) -> Option<String> {
+        self.push_error.clone()
+    }
+
+    /// Get notified about new data, status changes, laps and ride resets of
+    /// all sources instead of polling the getters of `Source`.
+    pub fn subscribe(&self) -> mpsc::Receiver<Notification> {
+        self.tap.events.subscribe()
+    }
+
+    /// Like `subscribe`, but only get notifications of the given kinds.
+    pub fn subscribe_to(&self, kinds: &[NotificationKind]) -> mpsc::Receiver<Notification> {
+        self.tap.events.subscribe_to(kinds)
+    }
+}
+
+impl Default for Facade {
+    fn default() -> Self {
+        Facade::new()
+    }
 }
@@ -1,4 +1,6 @@
-#[derive(Clone, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Zone {
     pub name: String,
     pub from: u32,
@@ -15,7 +17,7 @@ impl Zone {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct HrZones {
     pub zones: Vec<Zone>,
 }
@@ -88,7 +90,7 @@ impl HrZones {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct PwrZones {
     pub zones: Vec<Zone>,
 }
@@ -161,23 +163,68 @@ impl PwrZones {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// What the user tells about the athlete, see `Athlete::from_config`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AthleteConfig {
+    // heart rate threshold (bpm)
+    pub hr_threshold: u32,
+    // functional threshold power (W), also used as critical power
+    pub pwr_threshold: u32,
+    // anaerobic work capacity above the critical power (J)
+    pub w_prime: u32,
+    // kg
+    pub weight: f32,
+}
+
+impl AthleteConfig {
+    pub fn new() -> AthleteConfig {
+        AthleteConfig {
+            hr_threshold: 171,
+            pwr_threshold: 200,
+            w_prime: 20000,
+            weight: 61.0,
+        }
+    }
+}
+
+impl Default for AthleteConfig {
+    fn default() -> Self {
+        AthleteConfig::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Athlete {
     pub hr_threshold: u32,
     pub hr_zones: HrZones,
     pub pwr_threshold: u32,
     pub pwr_zones: PwrZones,
+    // anaerobic work capacity above the power threshold (J)
+    pub w_prime: u32,
     pub weight: f32,
 }
 
 impl Athlete {
     pub fn new() -> Athlete {
+        Athlete::from_config(&AthleteConfig::new())
+    }
+
+    /// The athlete as set by the user, with the zones derived from the thresholds.
+    pub fn from_config(config: &AthleteConfig) -> Athlete {
         Athlete { 
-            hr_threshold: 171, 
-            hr_zones: HrZones::create_from_threshold(171), 
-            pwr_threshold: 200, 
-            pwr_zones: PwrZones::create_from_threshold(200),
-            weight: 61.0,
+            hr_threshold: config.hr_threshold, 
+            hr_zones: HrZones::create_from_threshold(config.hr_threshold), 
+            pwr_threshold: config.pwr_threshold, 
+            pwr_zones: PwrZones::create_from_threshold(config.pwr_threshold),
+            w_prime: config.w_prime,
+            weight: config.weight,
         }
     }
 }
+
+impl Default for Athlete {
+    fn default() -> Self {
+        Athlete::new()
+    }
+}
@@ -1,10 +1,15 @@
-use crate::data::athlete::Athlete;
+use std::collections::VecDeque;
 
-#[derive(Clone, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::data::{athlete::Athlete, tpvbc::Received};
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Speed {
     pub cur: f32,
     pub max: f32,
     pub avg: f32,
+    pub history: Vec<f32>,
     first: bool,
 }
 
@@ -14,12 +19,14 @@ impl Speed {
             cur: 0.0,  
             max: 0.0, 
             avg: 0.0, 
+            history: vec![0.0],
             first: true,
         }
     }
 
     fn update(&mut self, focus: &super::tpvbc::Focus) {
         self.cur = focus.speed as f32  / 275.0;
+        self.history.push(self.cur);
 
         if self.first || self.cur > self.max {
             self.max = self.cur;
@@ -31,7 +38,13 @@ impl Speed {
     }
 }
 
-#[derive(Clone, PartialEq)]
+impl Default for Speed {
+    fn default() -> Self {
+        Speed::new()
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct HearRate {
     pub cur: u32,
     pub min: u32,
@@ -71,7 +84,13 @@ impl HearRate {
     }
 }
 
-#[derive(Clone, PartialEq)]
+impl Default for HearRate {
+    fn default() -> Self {
+        HearRate::new()
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Cadence {
     pub cur: u32,
     pub max: u32,
@@ -105,13 +124,23 @@ impl Cadence {
     }
 }
 
-#[derive(Clone, PartialEq)]
+impl Default for Cadence {
+    fn default() -> Self {
+        Cadence::new()
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Power {
     pub cur: u32,
     pub max: u32,
+    pub avg: u32,
     pub nrm: u32,
     pub wpk: f32,
     pub history: Vec<u32>,
+    // work (J) up to each second of the ride, for the best efforts
+    #[serde(skip)]
+    work: Vec<u64>,
     first: bool,
 }
 
@@ -120,29 +149,81 @@ impl Power {
         Power { 
             cur: 0,  
             max: 0, 
+            avg: 0,
             nrm: 0, 
             wpk: 0.0,
             history: vec![0],
+            work: vec![0],
             first: true,
         }
     }
 
-    fn update(&mut self, focus: &super::tpvbc::Focus, weight: f32) {
+    // a new sample, counting for the `secs` seconds since the one before
+    fn update(&mut self, focus: &super::tpvbc::Focus, weight: f32, secs: u32) {
         self.cur = focus.power;
         self.history.push(self.cur);
 
+        // samples are spread over the seconds since the sample before, so
+        // durations are real time even if samples were missed
+        for _ in 0..secs {
+            self.work.push(self.work[self.work.len() - 1] + self.cur as u64);
+        }
+
         if self.first || self.cur > self.max {
             self.max = self.cur;
         }
 
+        self.avg = focus.avgPower;
         self.nrm = focus.nrmPower; 
         self.wpk = self.cur as f32 / weight;
 
         self.first = false;
     }
+
+    /// Mean maximal power: the best average power (W) for durations (s) from
+    /// 1 s to the whole ride. Durations grow by about 10%, which is plenty for
+    /// a plot with a log axis.
+    pub fn mean_max(&self) -> Vec<(u32, f32)> {
+        let secs = self.work.len() - 1;
+
+        let mut curve = Vec::new();
+        let mut d = 1;
+
+        while d <= secs {
+            curve.push((d as u32, self.best(d)));
+
+            d = if d == secs {
+                d + 1
+            } else {
+                // always end with the whole ride
+                (d + 1).max(d * 11 / 10).min(secs)
+            };
+        }
+        curve
+    }
+
+    /// Best average power (W) over `secs` seconds, none if the ride is shorter.
+    pub fn best_average(&self, secs: u32) -> Option<f32> {
+        if secs == 0 || secs as usize >= self.work.len() {
+            return None;
+        }
+        Some(self.best(secs as usize))
+    }
+
+    fn best(&self, secs: usize) -> f32 {
+        let work = &self.work;
+        let best = (secs..work.len()).map(|end| work[end] - work[end - secs]).max().unwrap_or(0);
+        best as f32 / secs as f32
+    }
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Power::new()
+    }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Height {
     pub ascend: u32,
     pub slope: i32,
@@ -162,7 +243,13 @@ impl Height {
     }
 }
 
-#[derive(Clone, PartialEq)]
+impl Default for Height {
+    fn default() -> Self {
+        Height::new()
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Wind {
     pub speed: f32,
     pub angle: u32,
@@ -185,7 +272,295 @@ impl Wind {
     }
 }
 
-#[derive(Clone, PartialEq)]
+impl Default for Wind {
+    fn default() -> Self {
+        Wind::new()
+    }
+}
+
+// seconds of the rolling average of the normalized power
+const NP_WINDOW: usize = 30;
+
+/// Training load computed from the power samples. TPV sends its own values
+/// for the whole ride, see `Power::nrm` and `Metrics::tss`, but none for laps.
+#[derive(Clone, Default, PartialEq, Serialize)]
+pub struct Load {
+    // normalized power (W)
+    pub np: u32,
+    // intensity factor, NP / FTP
+    pub intensity: f32,
+    pub tss: f32,
+    // work (kJ)
+    pub kj: f32,
+    #[serde(skip)]
+    secs: u64,
+    #[serde(skip)]
+    joules: u64,
+    // power of the last seconds and their sum, for the rolling average
+    #[serde(skip)]
+    window: VecDeque<u32>,
+    #[serde(skip)]
+    window_sum: u64,
+    // sum and count of the rolling averages to the power of 4
+    #[serde(skip)]
+    sum4: f64,
+    #[serde(skip)]
+    count4: u64,
+}
+
+impl Load {
+    // `watts` were held for `secs`, `ftp` is the functional threshold power
+    fn add(&mut self, watts: u32, secs: u32, ftp: u32) {
+        for _ in 0..secs {
+            self.secs += 1;
+            self.joules += watts as u64;
+
+            self.window.push_back(watts);
+            self.window_sum += watts as u64;
+
+            if self.window.len() > NP_WINDOW {
+                self.window_sum -= self.window.pop_front().unwrap_or(0) as u64;
+            }
+
+            if self.window.len() == NP_WINDOW {
+                self.sum4 += (self.window_sum as f64 / NP_WINDOW as f64).powi(4);
+                self.count4 += 1;
+            }
+        }
+
+        self.kj = self.joules as f32 / 1000.0;
+
+        // not normalized before the first window is full
+        if self.count4 > 0 {
+            self.np = (self.sum4 / self.count4 as f64).powf(0.25).round() as u32;
+        }
+
+        if ftp > 0 {
+            self.intensity = self.np as f32 / ftp as f32;
+            self.tss = self.secs as f32 * self.np as f32 * self.intensity / (ftp as f32 * 3600.0) * 100.0;
+        }
+    }
+}
+
+// seconds with heart rate each half needs for the aerobic decoupling
+const DECOUPLING_MIN_HR: u64 = 30;
+
+/// Aerobic decoupling, how much less power (Pw:HR) or speed (Pa:HR) each
+/// heart beat gives in the second half of a ride (or window) than in the first.
+/// Below 5% on a long steady ride is a sign of good aerobic fitness.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Decoupling {
+    // ride time (s) of the window analyzed
+    pub from: u32,
+    pub to: u32,
+    // efficiency factor, NP / avg HR, of the window and of its halves
+    pub ef: f32,
+    pub ef_first: f32,
+    pub ef_second: f32,
+    // drift of power and of speed per heart beat (%)
+    pub pw_hr: f32,
+    pub pa_hr: f32,
+}
+
+impl Decoupling {
+    // power (W), heart rate (bpm) and speed (km/h) of each second
+    fn compute(seconds: &[(u32, u32, f32)], from: u32, to: u32) -> Option<Decoupling> {
+        let (first, second) = seconds.split_at(seconds.len() / 2);
+
+        let (ef, _) = Decoupling::efficiency(seconds)?;
+        let (ef_first, pa_first) = Decoupling::efficiency(first)?;
+        let (ef_second, pa_second) = Decoupling::efficiency(second)?;
+
+        Some(Decoupling {
+            from,
+            to,
+            ef,
+            ef_first,
+            ef_second,
+            pw_hr: (ef_first - ef_second) / ef_first * 100.0,
+            pa_hr: (pa_first - pa_second) / pa_first * 100.0,
+        })
+    }
+
+    // NP and speed per bpm, none without NP or with less than
+    // `DECOUPLING_MIN_HR` seconds of heart rate
+    fn efficiency(seconds: &[(u32, u32, f32)]) -> Option<(f32, f32)> {
+        let mut load = Load::default();
+        let mut hr: u64 = 0;
+        let mut speed: f64 = 0.0;
+        let mut secs: u64 = 0;
+
+        for (p, h, v) in seconds {
+            load.add(*p, 1, 0);
+
+            // no heart rate sent, e.g. without strap
+            if *h > 0 {
+                hr += *h as u64;
+                speed += *v as f64;
+                secs += 1;
+            }
+        }
+
+        if load.np == 0 || secs < DECOUPLING_MIN_HR || speed <= 0.0 {
+            return None;
+        }
+
+        let hr = hr as f32 / secs as f32;
+        Some((load.np as f32 / hr, (speed / secs as f64) as f32 / hr))
+    }
+}
+
+/// Remaining anaerobic work capacity (W' balance), differential model of
+/// Skiba et al. Above the critical power W' is used up joule by joule, below it
+/// recovers the faster the more of it is used up.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct WBalance {
+    // W' of the athlete (J)
+    pub capacity: u32,
+    // remaining W' (J), below 0 if CP or W' of the athlete are set too low
+    pub cur: f32,
+    pub min: f32,
+    // remaining W' (J) of each sample, like the other histories
+    pub history: Vec<f32>,
+}
+
+impl WBalance {
+    pub fn new(capacity: u32) -> WBalance {
+        WBalance {
+            capacity,
+            cur: capacity as f32,
+            min: capacity as f32,
+            history: vec![capacity as f32],
+        }
+    }
+
+    /// Remaining W' in percent of the capacity.
+    pub fn percent(&self) -> f32 {
+        if self.capacity > 0 {
+            self.cur / self.capacity as f32 * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    // `watts` were held for `secs`, `cp` is the critical power
+    fn add(&mut self, watts: u32, secs: u32, cp: u32) {
+        let capacity = self.capacity as f32;
+
+        for _ in 0..secs {
+            if watts > cp {
+                self.cur -= (watts - cp) as f32;
+            } else if capacity > 0.0 {
+                // a low W' with a high CP would overshoot in a single second
+                self.cur = (self.cur + (cp - watts) as f32 * (capacity - self.cur) / capacity).min(capacity);
+            }
+        }
+
+        self.min = self.min.min(self.cur);
+        self.history.push(self.cur);
+    }
+
+    // the W' used up so far stays used up
+    fn set_capacity(&mut self, capacity: u32) {
+        let delta = capacity as f32 - self.capacity as f32;
+
+        self.capacity = capacity;
+        self.cur += delta;
+        self.min += delta;
+    }
+}
+
+impl Default for WBalance {
+    fn default() -> Self {
+        WBalance::new(0)
+    }
+}
+
+/// Why a lap ended.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum LapTrigger {
+    /// TPV counted a lap of the event
+    Event,
+    /// The rider pressed the lap button
+    Manual,
+    /// The distance of an auto-lap was reached
+    Distance,
+    /// The time of an auto-lap was reached
+    Time,
+}
+
+/// When to start a new split by itself, a rule is off if 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AutoLap {
+    // km
+    pub distance: f32,
+    pub minutes: u32,
+}
+
+impl AutoLap {
+    fn due(&self, split: &Metrics) -> Option<LapTrigger> {
+        if self.distance > 0.0 && split.distance >= self.distance {
+            Some(LapTrigger::Distance)
+        } else if self.minutes > 0 && split.time >= self.minutes * 60 {
+            Some(LapTrigger::Time)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where a lap started, the lap metrics count from there.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LapStart {
+    pub time: u32,
+    pub distance: f32,
+    pub ascend: u32,
+    pub tss: u32,
+    pub calories: u32,
+}
+
+impl LapStart {
+    // a lap starting where `metrics` (of the whole ride) are now
+    fn at(metrics: &Metrics) -> LapStart {
+        LapStart {
+            time: metrics.time,
+            distance: metrics.distance,
+            ascend: metrics.height.ascend,
+            tss: metrics.tss,
+            calories: metrics.calories,
+        }
+    }
+}
+
+// running sums for the averages of a lap, weighted by time
+#[derive(Clone, Default, PartialEq)]
+struct Sums {
+    secs: u64,
+    hr: u64,
+    cadence: u64,
+    power: u64,
+}
+
+impl Sums {
+    fn add(&mut self, focus: &super::tpvbc::Focus, secs: u32) {
+        let secs = secs as u64;
+
+        self.secs += secs;
+        self.hr += focus.heartrate as u64 * secs;
+        self.cadence += focus.cadence as u64 * secs;
+        self.power += focus.power as u64 * secs;
+    }
+
+    fn avg(&self, sum: u64) -> u32 {
+        if self.secs == 0 {
+            return 0;
+        }
+        (sum / self.secs) as u32
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Metrics {
     pub time: u32,
     pub distance: f32,
@@ -198,6 +573,18 @@ pub struct Metrics {
     pub height: Height,
     pub lap: u32,
     pub wind: Wind,
+    pub time_in_hr_zones: TimeInZones,
+    pub time_in_pwr_zones: TimeInZones,
+    pub load: Load,
+    // when each sample of the histories was received
+    pub timestamps: Vec<Received>,
+    // ride time (s) of each sample of the histories
+    pub times: Vec<u32>,
+    // only set for laps, the whole ride takes its aggregates from TPV
+    pub start: Option<LapStart>,
+    pub ended_by: Option<LapTrigger>,
+    #[serde(skip)]
+    sums: Sums,
 }
 
 impl Metrics {
@@ -214,6 +601,23 @@ impl Metrics {
             height: Height::new(),
             lap: 0,
             wind: Wind::new(),
+            time_in_hr_zones: TimeInZones::new(7),
+            time_in_pwr_zones: TimeInZones::new(7),
+            load: Load::default(),
+            timestamps: vec![Received::default()],
+            times: vec![0],
+            start: None,
+            ended_by: None,
+            sums: Sums::default(),
+        }
+    }
+
+    /// Metrics of a lap starting at `start`.
+    pub fn lap(lap: u32, start: LapStart) -> Metrics {
+        Metrics {
+            lap,
+            start: Some(start),
+            ..Metrics::new()
         }
     }
 
@@ -224,9 +628,91 @@ impl Metrics {
 
         format!("{:02}:{:02}:{:02}", h, m, s)
     }
+
+    /// Best average power for durations from 1 s to the whole ride, see `Power::mean_max`.
+    pub fn power_curve(&self) -> Vec<(u32, f32)> {
+        self.power.mean_max()
+    }
+
+    /// Best average power over `secs` seconds, see `Power::best_average`.
+    pub fn best_power(&self, secs: u32) -> Option<f32> {
+        self.power.best_average(secs)
+    }
+
+    /// Aerobic decoupling between the ride time `from` and `to` (s), none if
+    /// a half has less than 30 s with heart rate.
+    pub fn decoupling(&self, from: u32, to: u32) -> Option<Decoupling> {
+        let mut seconds = Vec::new();
+        let n = self.times.len().min(self.power.history.len()).min(self.hr.history.len()).min(self.speed.history.len());
+
+        for i in 1..n {
+            if self.times[i] <= from || self.times[i] > to {
+                continue;
+            }
+
+            // like the power curve, a sample counts for the seconds since the one before
+            let secs = if i == 1 { 1 } else { self.times[i].saturating_sub(self.times[i - 1]) };
+
+            for _ in 0..secs {
+                seconds.push((self.power.history[i], self.hr.history[i], self.speed.history[i]));
+            }
+        }
+
+        Decoupling::compute(&seconds, from, to.min(self.times[n - 1]))
+    }
+
+    // a new sample, `secs` after the last one, `first` if it is the first one of the ride
+    fn update(&mut self, focus: &super::tpvbc::Focus, athlete: &Athlete, received: Received, secs: u32, first: bool) {
+        // the first sample may be far into the ride if joined late, it counts for a second
+        // only. Laps and splits started later go on from the sample before. Zones, averages,
+        // load and power curve all use the same time.
+        let counted = if first { 1 } else { secs };
+
+        self.time_in_hr_zones.add_time(athlete.hr_zones.zone(focus.heartrate), counted);
+        self.time_in_pwr_zones.add_time(athlete.pwr_zones.zone(focus.power), counted);
+        self.load.add(focus.power, counted, athlete.pwr_threshold);
+
+        self.time = focus.time;
+        self.distance = (focus.distance as f32) / 1000.0;
+        self.tss = focus.tss;
+        self.calories = focus.calories;
+
+        self.speed.update(focus);
+        self.hr.update(focus);
+        self.cadence.update(focus);
+        self.power.update(focus, athlete.weight, counted);
+        self.height.update(focus);
+        self.timestamps.push(received);
+        self.times.push(focus.time);
+
+        if let Some(start) = &self.start {
+            self.time = self.time.saturating_sub(start.time);
+            self.distance = (self.distance - start.distance).max(0.0);
+            self.tss = self.tss.saturating_sub(start.tss);
+            self.calories = self.calories.saturating_sub(start.calories);
+            self.height.ascend = self.height.ascend.saturating_sub(start.ascend);
+
+            self.sums.add(focus, counted);
+            self.hr.avg = self.sums.avg(self.sums.hr);
+            self.cadence.avg = self.sums.avg(self.sums.cadence);
+            self.power.avg = self.sums.avg(self.sums.power);
+            // TPV only tells the normalized power of the whole ride, see `load` instead
+            self.power.nrm = 0;
+
+            if self.time > 0 {
+                self.speed.avg = self.distance / self.time as f32 * 3600.0;
+            }
+        }
+    }
 }
 
-#[derive(Clone, PartialEq)]
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct TimeInZones {
     pub zones: Vec<u32>,
 }
@@ -284,60 +770,117 @@ impl TimeInZones {
     }
 }
 
-#[derive(Clone, PartialEq)]
+/// The current values of the ride, without the histories, laps and splits
+/// which grow with the ride. Small enough to be sent with every sample.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub time: u32,
+    pub distance: f32,
+    pub speed: f32,
+    pub power: u32,
+    pub wpk: f32,
+    pub hr: u32,
+    pub cadence: u32,
+    pub ascend: u32,
+    pub slope: i32,
+    pub lap: u32,
+    pub calories: u32,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
 pub struct Ride {
     pub athlete: Athlete,
     pub total: Metrics,
+    // laps of the event as counted by TPV
     pub current_lap: Metrics,
     pub past_laps: Vec<Metrics>,
-    pub time_in_hr_zones: TimeInZones,
-    pub time_in_pwr_zones: TimeInZones,
+    // laps started by hand or auto-lap, independent of the event
+    pub current_split: Metrics,
+    pub past_splits: Vec<Metrics>,
+    pub auto_lap: AutoLap,
+    // of the whole ride, its history goes with the one of `total`
+    pub w_balance: WBalance,
+    // of the whole ride, first half against second half
+    pub decoupling: Option<Decoupling>,
 }
 
 impl Ride {
     pub fn new() -> Ride {
+        Ride::with_athlete(Athlete::new())
+    }
+
+    pub fn with_athlete(athlete: Athlete) -> Ride {
         Ride {
-            athlete: Athlete::new(),
+            w_balance: WBalance::new(athlete.w_prime),
+            decoupling: None,
+            athlete,
             total: Metrics::new(),
-            current_lap: Metrics::new(),
+            current_lap: Metrics::lap(0, LapStart::default()),
             past_laps: Vec::new(),
-            time_in_hr_zones: TimeInZones::new(7),
-            time_in_pwr_zones: TimeInZones::new(7),
+            current_split: Metrics::lap(1, LapStart::default()),
+            past_splits: Vec::new(),
+            auto_lap: AutoLap::default(),
         }
     }
 
     pub fn reset(&mut self) {
         log::info!("Restting ride data!");
         self.total = Metrics::new();
-        self.current_lap = Metrics::new();
+        self.current_lap = Metrics::lap(0, LapStart::default());
         self.past_laps = Vec::new();
-        self.time_in_hr_zones = TimeInZones::new(7);
-        self.time_in_pwr_zones = TimeInZones::new(7);
+        self.current_split = Metrics::lap(1, LapStart::default());
+        self.past_splits = Vec::new();
+        self.w_balance = WBalance::new(self.athlete.w_prime);
+        self.decoupling = None;
     }
 
-    pub fn update(&mut self, focus: super::tpvbc::Focus) {
+    pub fn update(&mut self, focus: super::tpvbc::Focus, received: Received) {
         // make sure we have not seen this data before
         if self.total.time < focus.time {
             let delta_to_last_total_time = focus.time - self.total.time;
-            let hr_zone = self.athlete.hr_zones.zone(focus.heartrate);
-            let pwr_zone = self.athlete.pwr_zones.zone(focus.power);
-            self.time_in_hr_zones.add_time(hr_zone, delta_to_last_total_time);
-            self.time_in_pwr_zones.add_time(pwr_zone, delta_to_last_total_time);
+            let new_minute = focus.time / 60 != self.total.time / 60;
+
+            let lap = if focus.eventLapsDone >= 0 {
+                focus.eventLapsDone as u32 + 1
+            } else {
+                self.total.lap
+            };
+
+            // the lap counter went up, the next lap starts where this one ended
+            if lap > self.current_lap.lap && self.current_lap.lap > 0 {
+                let mut next = Metrics::lap(lap, LapStart::at(&self.total));
+                std::mem::swap(&mut self.current_lap, &mut next);
+                next.ended_by = Some(LapTrigger::Event);
+                self.past_laps.push(next);
+            }
+
+            // like the power curve, the first sample counts for a second only
+            let first = self.total.times.len() == 1;
+            let secs = if first { 1 } else { delta_to_last_total_time };
+            self.w_balance.add(focus.power, secs, self.athlete.pwr_threshold);
 
+            self.total.update(&focus, &self.athlete, received, delta_to_last_total_time, first);
 
-            self.total.time = focus.time;
-            self.total.distance = (focus.distance as f32) / 1000.0;
-            self.total.tss = focus.tss;
-            self.total.calories = focus.calories;
+            if self.current_lap.lap == 0 && lap > 1 {
+                // joined during a later lap, what happened before belongs to laps not seen
+                self.current_lap = Metrics::lap(lap, LapStart::at(&self.total));
+            } else {
+                self.current_lap.update(&focus, &self.athlete, received, delta_to_last_total_time, first);
+            }
+
+            // goes through the whole ride, once a minute is enough
+            if new_minute {
+                self.decoupling = self.total.decoupling(0, u32::MAX);
+            }
 
-            self.total.speed.update(&focus);
-            self.total.hr.update(&focus); 
-            self.total.cadence.update(&focus);
-            self.total.power.update(&focus, self.athlete.weight);
-            self.total.height.update(&focus);
+            self.total.lap = lap;
+            self.current_lap.lap = lap;
 
-            if focus.eventLapsDone >= 0 {
-                self.total.lap = focus.eventLapsDone as u32 + 1;
+            // a split ends with the sample reaching the auto-lap distance or time
+            self.current_split.update(&focus, &self.athlete, received, delta_to_last_total_time, first);
+
+            if let Some(trigger) = self.auto_lap.due(&self.current_split) {
+                self.split(trigger);
             }
         } else if focus.time < self.total.time {
             log::info!("Looks like a new ride has started.");
@@ -345,5 +888,416 @@ impl Ride {
         }
         // wind data could always change in TPV, also when ride was not started yet
         self.total.wind.update(&focus);
+        self.current_lap.wind.update(&focus);
+        self.current_split.wind.update(&focus);
+    }
+
+    /// Change the athlete, e.g. because the user corrected the FTP. Zones and
+    /// load of the samples so far are kept.
+    pub fn set_athlete(&mut self, athlete: Athlete) {
+        if self.athlete == athlete {
+            return;
+        }
+
+        self.w_balance.set_capacity(athlete.w_prime);
+        self.athlete = athlete;
+    }
+
+    /// End the current split and start a new one, like the lap button of a bike computer.
+    pub fn lap(&mut self) {
+        // nothing to split before the ride started
+        if self.current_split.time > 0 {
+            self.split(LapTrigger::Manual);
+        }
+    }
+
+    fn split(&mut self, trigger: LapTrigger) {
+        let mut next = Metrics::lap(self.current_split.lap + 1, LapStart::at(&self.total));
+        std::mem::swap(&mut self.current_split, &mut next);
+        next.ended_by = Some(trigger);
+        log::info!("Split {} ended ({:?})", next.lap, trigger);
+        self.past_splits.push(next);
+    }
+
+    /// The current values, see `Snapshot`.
+    pub fn snapshot(&self) -> Snapshot {
+        let m = &self.total;
+
+        Snapshot {
+            time: m.time,
+            distance: m.distance,
+            speed: m.speed.cur,
+            power: m.power.cur,
+            wpk: m.power.wpk,
+            hr: m.hr.cur,
+            cadence: m.cadence.cur,
+            ascend: m.height.ascend,
+            slope: m.height.slope,
+            lap: m.lap,
+            calories: m.calories,
+        }
+    }
+
+    /// All laps, the completed ones and the current one.
+    pub fn laps(&self) -> Vec<&Metrics> {
+        self.past_laps.iter().chain(std::iter::once(&self.current_lap)).collect()
+    }
+
+    /// All splits, the completed ones and the current one.
+    pub fn splits(&self) -> Vec<&Metrics> {
+        self.past_splits.iter().chain(std::iter::once(&self.current_split)).collect()
+    }
+}
+
+impl Default for Ride {
+    fn default() -> Self {
+        Ride::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tpvbc::Focus;
+
+    // feed `(time, power, heartrate)` samples
+    fn feed(ride: &mut Ride, samples: &[(u32, u32, u32)]) {
+        for (time, power, heartrate) in samples {
+            let mut focus = Focus::new();
+            focus.time = *time;
+            focus.power = *power;
+            focus.heartrate = *heartrate;
+            focus.speed = 10000;
+            // 10 m/s, 36 km/h
+            focus.distance = *time * 10;
+            ride.update(focus, Received::default());
+        }
+    }
+
+    fn ride(samples: &[(u32, u32, u32)]) -> Ride {
+        let mut ride = Ride::new();
+        feed(&mut ride, samples);
+        ride
+    }
+
+    // `power` from second `from` to `to`
+    fn steady(from: u32, to: u32, power: u32) -> Vec<(u32, u32, u32)> {
+        (from..=to).map(|t| (t, power, 140)).collect()
+    }
+
+    #[test]
+    fn mean_max_of_constant_power_is_flat() {
+        let ride = ride(&steady(1, 600, 250));
+        let curve = ride.total.power_curve();
+
+        assert_eq!(curve.first(), Some(&(1, 250.0)));
+        assert_eq!(curve.last(), Some(&(600, 250.0)));
+        assert!(curve.iter().all(|(_, w)| *w == 250.0));
+    }
+
+    #[test]
+    fn mean_max_gap_does_not_inflate() {
+        // 30 s at 300 W twice, with half a minute at 0 W only told by a single sample
+        let mut samples = steady(1, 30, 300);
+        samples.push((60, 0, 140));
+        samples.extend(steady(61, 90, 300));
+
+        let ride = ride(&samples);
+
+        assert_eq!(ride.total.best_power(30), Some(300.0));
+        assert_eq!(ride.total.best_power(60), Some(150.0));
+        assert_eq!(ride.total.power_curve().last(), Some(&(90, 200.0)));
+    }
+
+    #[test]
+    fn mean_max_late_join_counts_from_first_sample() {
+        let ride = ride(&steady(3600, 3659, 250));
+
+        assert_eq!(ride.total.best_power(60), Some(250.0));
+        assert_eq!(ride.total.best_power(61), None);
+        assert_eq!(ride.total.power_curve().last(), Some(&(60, 250.0)));
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn load_of_an_hour_at_ftp() {
+        let ftp = Athlete::new().pwr_threshold;
+        let ride = ride(&steady(1, 3600, ftp));
+        let load = &ride.total.load;
+
+        assert_eq!(load.np, ftp);
+        assert!(close(load.intensity, 1.0));
+        assert!(close(load.tss, 100.0));
+    }
+
+    #[test]
+    fn load_work_is_power_times_seconds() {
+        // the sample at 20 s tells the power of the 10 s since the one before
+        let mut samples = steady(1, 10, 100);
+        samples.push((20, 300, 140));
+
+        let ride = ride(&samples);
+
+        assert!(close(ride.total.load.kj, (10.0 * 100.0 + 10.0 * 300.0) / 1000.0));
+    }
+
+    #[test]
+    fn load_of_splits() {
+        let ftp = Athlete::new().pwr_threshold;
+        let mut ride = ride(&steady(1, 1800, ftp));
+        ride.lap();
+        feed(&mut ride, &steady(1801, 3600, ftp / 2));
+
+        let first = &ride.past_splits[0].load;
+        assert_eq!(first.np, ftp);
+        assert!(close(first.intensity, 1.0));
+        assert!(close(first.tss, 50.0));
+        assert!(close(first.kj, 1800.0 * ftp as f32 / 1000.0));
+
+        let second = &ride.current_split.load;
+        assert_eq!(second.np, ftp / 2);
+        assert!(close(second.intensity, 0.5));
+        assert!(close(second.tss, 12.5));
+        assert!(close(second.kj, 1800.0 * (ftp / 2) as f32 / 1000.0));
+
+        assert!(close(ride.total.load.kj, first.kj + second.kj));
+    }
+
+    #[test]
+    fn decoupling_compares_the_halves() {
+        // same power, but the heart rate drifts up in the second half
+        let mut samples = steady(1, 300, 200);
+        samples.extend((301..=600).map(|t| (t, 200, 147)));
+
+        let ride = ride(&samples);
+        let d = ride.total.decoupling(0, u32::MAX).unwrap();
+
+        assert_eq!((d.from, d.to), (0, 600));
+        assert!(close(d.ef_first, 200.0 / 140.0));
+        assert!(close(d.ef_second, 200.0 / 147.0));
+        assert!(close(d.pw_hr, (1.0 - 140.0 / 147.0) * 100.0));
+        assert!(close(d.pa_hr, d.pw_hr));
+    }
+
+    #[test]
+    fn decoupling_of_a_window() {
+        // a warm up at a lower heart rate, then a steady part drifting
+        let mut samples: Vec<_> = (1..=600).map(|t| (t, 200, 100)).collect();
+        samples.extend(steady(601, 900, 200));
+        samples.extend((901..=1200).map(|t| (t, 200, 147)));
+
+        let ride = ride(&samples);
+        let d = ride.total.decoupling(600, 1200).unwrap();
+
+        assert_eq!((d.from, d.to), (600, 1200));
+        assert!(close(d.pw_hr, (1.0 - 140.0 / 147.0) * 100.0));
+
+        // the whole ride compares the warm up with the steady part
+        let whole = ride.total.decoupling(0, u32::MAX).unwrap();
+        assert!(close(whole.pw_hr, (1.0 - 100.0 / 143.5) * 100.0));
+    }
+
+    #[test]
+    fn decoupling_efficiency_factor_is_np_per_heartrate() {
+        let samples: Vec<_> = (1..=600).map(|t| (t, 200, if t % 2 == 0 { 140 } else { 160 })).collect();
+
+        let ride = ride(&samples);
+        let d = ride.total.decoupling(0, u32::MAX).unwrap();
+
+        assert!(close(d.ef, 200.0 / 150.0));
+    }
+
+    #[test]
+    fn decoupling_needs_heart_rate_in_both_halves() {
+        // the strap drops out in the second half, 20 s and then 30 s with heart rate
+        let mut samples = steady(1, 60, 200);
+        samples.extend((61..=120).map(|t| (t, 200, if t <= 80 { 140 } else { 0 })));
+        assert_eq!(ride(&samples).total.decoupling(0, u32::MAX), None);
+
+        let mut samples = steady(1, 60, 200);
+        samples.extend((61..=120).map(|t| (t, 200, if t <= 90 { 140 } else { 0 })));
+        assert!(ride(&samples).total.decoupling(0, u32::MAX).is_some());
+    }
+
+    // `power` every 5 s from second `from` to `to`
+    fn every_5s(from: u32, to: u32, power: u32) -> Vec<(u32, u32, u32)> {
+        (from..=to).step_by(5).map(|t| (t, power, 140)).collect()
+    }
+
+    #[test]
+    fn manual_split_counts_from_the_sample_before() {
+        let mut ride = ride(&every_5s(5, 600, 200));
+        ride.lap();
+        feed(&mut ride, &every_5s(605, 1200, 100));
+
+        let first = &ride.past_splits[0];
+        let second = &ride.current_split;
+
+        assert_eq!(first.ended_by, Some(LapTrigger::Manual));
+        assert!(close(second.load.kj, 600.0 * 100.0 / 1000.0));
+        assert_eq!(second.best_power(600), Some(100.0));
+        assert_eq!(second.best_power(601), None);
+        assert!(close(ride.total.load.kj, first.load.kj + second.load.kj));
+    }
+
+    #[test]
+    fn auto_split_by_distance() {
+        let mut ride = Ride::new();
+        ride.auto_lap = AutoLap { distance: 1.0, minutes: 0 };
+        feed(&mut ride, &every_5s(5, 600, 200));
+
+        // 1 km takes 100 s
+        assert_eq!(ride.past_splits.len(), 6);
+
+        for split in ride.past_splits.iter().skip(1) {
+            assert_eq!(split.ended_by, Some(LapTrigger::Distance));
+            assert!(close(split.distance, 1.0));
+            assert!(close(split.load.kj, 100.0 * 200.0 / 1000.0));
+            assert_eq!(split.best_power(100), Some(200.0));
+        }
+    }
+
+    #[test]
+    fn auto_split_by_time() {
+        let mut ride = Ride::new();
+        ride.auto_lap = AutoLap { distance: 0.0, minutes: 5 };
+        feed(&mut ride, &every_5s(5, 900, 200));
+
+        assert_eq!(ride.past_splits.len(), 3);
+
+        for split in ride.past_splits.iter().skip(1) {
+            assert_eq!(split.ended_by, Some(LapTrigger::Time));
+            assert_eq!(split.time, 300);
+            assert!(close(split.load.kj, 300.0 * 200.0 / 1000.0));
+            assert_eq!(split.best_power(300), Some(200.0));
+        }
+    }
+
+    // feed `(time, laps done, power, heartrate)` samples
+    fn feed_laps(ride: &mut Ride, samples: &[(u32, i32, u32, u32)]) {
+        for (time, laps_done, power, heartrate) in samples {
+            let mut focus = Focus::new();
+            focus.time = *time;
+            focus.eventLapsDone = *laps_done;
+            focus.power = *power;
+            focus.heartrate = *heartrate;
+            ride.update(focus, Received::default());
+        }
+    }
+
+    #[test]
+    fn lap_closes_when_tpv_counts_a_lap() {
+        let mut ride = Ride::new();
+        feed_laps(&mut ride, &(1..=100).map(|t| (t, 0, 200, 140)).collect::<Vec<_>>());
+        feed_laps(&mut ride, &(101..=150).map(|t| (t, 1, 100, 120)).collect::<Vec<_>>());
+        feed_laps(&mut ride, &(151..=160).map(|t| (t, 2, 300, 170)).collect::<Vec<_>>());
+
+        assert_eq!(ride.past_laps.len(), 2);
+        assert!(ride.past_laps.iter().all(|l| l.ended_by == Some(LapTrigger::Event)));
+
+        let laps = ride.laps();
+        assert_eq!(laps.iter().map(|l| l.lap).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(laps.iter().map(|l| l.time).collect::<Vec<_>>(), vec![100, 50, 10]);
+        assert_eq!(laps.iter().map(|l| l.power.avg).collect::<Vec<_>>(), vec![200, 100, 300]);
+        assert_eq!(laps.iter().map(|l| l.hr.avg).collect::<Vec<_>>(), vec![140, 120, 170]);
+        assert_eq!(laps[2].ended_by, None);
+    }
+
+    #[test]
+    fn late_join_averages() {
+        // the first sample counts for a second, like for load and power curve
+        let mut samples = vec![(3600, 0, 100, 100)];
+        samples.extend((3601..=3659).map(|t| (t, 0, 200, 160)));
+
+        let mut ride = Ride::new();
+        feed_laps(&mut ride, &samples);
+        let lap = &ride.current_lap;
+
+        assert_eq!(lap.power.avg, (100 + 200 * 59) / 60);
+        assert_eq!(lap.hr.avg, (100 + 160 * 59) / 60);
+        assert_eq!(lap.time_in_pwr_zones.zones.iter().sum::<u32>(), 60);
+        assert!(close(lap.load.kj, (100.0 + 200.0 * 59.0) / 1000.0));
+
+        // joined in the third lap, the lap starts with the first sample
+        let mut samples = vec![(3600, 2, 100, 100)];
+        samples.extend((3601..=3659).map(|t| (t, 2, 200, 160)));
+
+        let mut ride = Ride::new();
+        feed_laps(&mut ride, &samples);
+        let lap = &ride.current_lap;
+
+        assert_eq!(lap.lap, 3);
+        assert_eq!(lap.power.avg, 200);
+        assert_eq!(lap.hr.avg, 160);
+        assert_eq!(lap.time, 59);
+    }
+
+    #[test]
+    fn w_balance_depletes_above_cp() {
+        let mut w_bal = WBalance::new(20000);
+        w_bal.add(300, 60, 200);
+
+        assert!(close(w_bal.cur, 20000.0 - 100.0 * 60.0));
+        assert!(close(w_bal.min, w_bal.cur));
+        assert!(close(w_bal.percent(), 70.0));
+    }
+
+    #[test]
+    fn w_balance_recovers_below_cp() {
+        let mut w_bal = WBalance::new(20000);
+        w_bal.add(300, 60, 200);
+
+        // the more is used up, the faster it comes back
+        w_bal.add(100, 1, 200);
+        assert!(close(w_bal.cur, 14000.0 + 100.0 * 6000.0 / 20000.0));
+
+        let mut last = w_bal.cur;
+        // 200 s time constant with 100 W below CP
+        for _ in 0..1200 {
+            w_bal.add(100, 1, 200);
+            assert!(w_bal.cur > last);
+            last = w_bal.cur;
+        }
+        assert!(w_bal.cur > 19900.0);
+        assert!(close(w_bal.min, 14000.0));
+    }
+
+    #[test]
+    fn w_balance_is_capped_at_capacity() {
+        let mut w_bal = WBalance::new(20000);
+        w_bal.add(0, 3600, 200);
+        assert!(w_bal.cur <= 20000.0);
+
+        // recovering more than W' in a second
+        let mut w_bal = WBalance::new(100);
+        w_bal.add(350, 1, 300);
+        w_bal.add(0, 10, 300);
+        assert!(close(w_bal.cur, 100.0));
+    }
+
+    #[test]
+    fn w_balance_keeps_what_is_used_up_on_a_new_capacity() {
+        let mut w_bal = WBalance::new(20000);
+        w_bal.add(300, 60, 200);
+        w_bal.set_capacity(25000);
+
+        assert_eq!(w_bal.capacity, 25000);
+        assert!(close(w_bal.cur, 19000.0));
+        assert!(close(w_bal.min, 19000.0));
+        assert!(close(w_bal.percent(), 76.0));
+
+        // the same through the athlete of a ride
+        let mut ride = ride(&steady(1, 60, 300));
+        let used = ride.athlete.w_prime as f32 - ride.w_balance.cur;
+
+        let mut athlete = ride.athlete.clone();
+        athlete.w_prime += 5000;
+        ride.set_athlete(athlete);
+
+        assert_eq!(ride.w_balance.capacity, ride.athlete.w_prime);
+        assert!(close(ride.athlete.w_prime as f32 - ride.w_balance.cur, used));
     }
 }
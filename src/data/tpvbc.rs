@@ -1,46 +1,115 @@
-use std::sync::{Arc, Mutex};
-use serde::Deserialize;
-
-use super::ride::Ride;
-
-pub mod interface;
-pub mod httpclient;
-pub mod filesystem;
-
-#[derive(Debug, Deserialize, Clone)]
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    thread,
+    time,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{athlete::Athlete, ride::{AutoLap, Ride}};
+use events::Events;
+use schema::lenient;
+
+pub(crate) mod interface;
+pub(crate) mod config;
+pub(crate) mod credentials;
+#[cfg(feature = "http-source")]
+pub(crate) mod httpclient;
+#[cfg(feature = "fs-source")]
+pub(crate) mod filesystem;
+pub(crate) mod schema;
+pub(crate) mod recorder;
+#[cfg(feature = "serve")]
+pub(crate) mod server;
+#[cfg(any(feature = "gui", feature = "serve"))]
+pub(crate) mod discovery;
+#[cfg(feature = "serve")]
+pub(crate) mod push;
+pub(crate) mod tap;
+pub(crate) mod events;
+pub(crate) mod replay;
+pub(crate) mod simulator;
+
+pub use config::{BcastConfig, PushConfig, ServerConfig};
+pub use credentials::{Auth, Credentials};
+pub use events::{Notification, NotificationKind};
+pub use interface::BcastStreamIf;
+pub use replay::ReplayState;
+pub use schema::{Extra, SchemaInfo};
+
+/// The TPV bcast streams, named like their files.
+pub(crate) const STREAMS: [&str; 7] = ["focus", "nearest", "event", "entries", "groups", "resultsIndv", "resultsTeam"];
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 #[allow(dead_code, non_snake_case)]
 pub struct Focus {
+    #[serde(deserialize_with = "lenient")]
     pub name: String,
+    #[serde(deserialize_with = "lenient")]
     pub country: String,
+    #[serde(deserialize_with = "lenient")]
     pub team: String,
+    #[serde(deserialize_with = "lenient")]
     pub teamCode: String,
+    #[serde(deserialize_with = "lenient")]
     pub power: u32,
+    #[serde(deserialize_with = "lenient")]
     pub avgPower: u32,
+    #[serde(deserialize_with = "lenient")]
     pub nrmPower: u32,
+    #[serde(deserialize_with = "lenient")]
     pub maxPower: u32,
+    #[serde(deserialize_with = "lenient")]
     pub cadence: u32,
+    #[serde(deserialize_with = "lenient")]
     pub avgCadence: u32,
+    #[serde(deserialize_with = "lenient")]
     pub maxCadence: u32,
+    #[serde(deserialize_with = "lenient")]
     pub heartrate: u32,
+    #[serde(deserialize_with = "lenient")]
     pub avgHeartrate: u32,
+    #[serde(deserialize_with = "lenient")]
     pub maxHeartrate: u32,
+    #[serde(deserialize_with = "lenient")]
     pub time: u32,
+    #[serde(deserialize_with = "lenient")]
     pub distance: u32,
+    #[serde(deserialize_with = "lenient")]
     pub height: u32,
+    #[serde(deserialize_with = "lenient")]
     pub speed: u32,
+    #[serde(deserialize_with = "lenient")]
     pub tss: u32,
+    #[serde(deserialize_with = "lenient")]
     pub calories: u32,
+    #[serde(deserialize_with = "lenient")]
     pub draft: u32,
+    #[serde(deserialize_with = "lenient")]
     pub windSpeed: u32,
+    #[serde(deserialize_with = "lenient")]
     pub windAngle: u32,
+    #[serde(deserialize_with = "lenient")]
     pub slope: i32,
+    #[serde(deserialize_with = "lenient")]
     pub eventLapsTotal: u32,
+    #[serde(deserialize_with = "lenient")]
     pub eventLapsDone: i32,
+    #[serde(deserialize_with = "lenient")]
     pub eventDistanceTotal: u32,
+    #[serde(deserialize_with = "lenient")]
     pub eventDistanceDone: u32,
+    #[serde(deserialize_with = "lenient")]
     pub eventDistanceToNextLocation: u32,
+    #[serde(deserialize_with = "lenient")]
     pub eventNextLocation: u32,
+    #[serde(deserialize_with = "lenient")]
     pub eventPosition: u32,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl Focus {
@@ -77,22 +146,41 @@ impl Focus {
             eventDistanceToNextLocation: 0,
             eventNextLocation: 0,
             eventPosition: 0,
+            extra: Extra::new(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for Focus {
+    fn default() -> Self {
+        Focus::new()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 #[allow(dead_code, non_snake_case)]
 pub struct Nearest {
+    #[serde(deserialize_with = "lenient")]
     pub name: String,
+    #[serde(deserialize_with = "lenient")]
     pub country: String,
+    #[serde(deserialize_with = "lenient")]
     pub team: String,
+    #[serde(deserialize_with = "lenient")]
     pub teamCode: String,
+    #[serde(deserialize_with = "lenient")]
     pub speed: u32,
+    #[serde(deserialize_with = "lenient")]
     pub timeGap: i32,
+    #[serde(deserialize_with = "lenient")]
     pub position: u32,
+    #[serde(deserialize_with = "lenient")]
     pub distance: u32,
-    pub isEliminated: bool,    
+    #[serde(deserialize_with = "lenient")]
+    pub isEliminated: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl Nearest {
@@ -107,20 +195,37 @@ impl Nearest {
             position: 0,
             distance: 0,
             isEliminated: false,    
+            extra: Extra::new(),
         }
     }    
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for Nearest {
+    fn default() -> Self {
+        Nearest::new()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 #[allow(dead_code, non_snake_case)]
 pub struct Event{
+    #[serde(deserialize_with = "lenient")]
     pub name: String,
+    #[serde(deserialize_with = "lenient")]
     pub route: String,
+    #[serde(deserialize_with = "lenient")]
     pub laps: u32,
+    #[serde(deserialize_with = "lenient")]
     pub distance: u32,
+    #[serde(deserialize_with = "lenient")]
     pub height: u32,
+    #[serde(deserialize_with = "lenient")]
     pub locations: u32,
-    pub type_: String, 
+    #[serde(rename = "type", deserialize_with = "lenient")]
+    pub type_: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl Event {
@@ -133,18 +238,33 @@ impl Event {
             height: 0,
             locations: 0,
             type_: String::from("--"), 
+            extra: Extra::new(),
         }
     }    
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for Event {
+    fn default() -> Self {
+        Event::new()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 #[allow(dead_code, non_snake_case)]
 pub struct Entries {
+    #[serde(deserialize_with = "lenient")]
     pub bibNum: u32,
+    #[serde(deserialize_with = "lenient")]
     pub name: String,
+    #[serde(deserialize_with = "lenient")]
     pub country: String,
+    #[serde(deserialize_with = "lenient")]
     pub team: String,
-    pub teamCode: String,    
+    #[serde(deserialize_with = "lenient")]
+    pub teamCode: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl Entries {
@@ -155,20 +275,37 @@ impl Entries {
             country: String::from("--"),
             team: String::from("--"),
             teamCode: String::from("--"),  
+            extra: Extra::new(),
         }
     }    
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for Entries {
+    fn default() -> Self {
+        Entries::new()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 #[allow(dead_code, non_snake_case)]
 pub struct Groups {
+    #[serde(deserialize_with = "lenient")]
     pub groupNum1: u32,
+    #[serde(deserialize_with = "lenient")]
     pub groupNum2: u32,
+    #[serde(deserialize_with = "lenient")]
     pub leader: String,
+    #[serde(deserialize_with = "lenient")]
     pub size: u32,
+    #[serde(deserialize_with = "lenient")]
     pub timeGap1: i32,
+    #[serde(deserialize_with = "lenient")]
     pub timeGap2: i32,
+    #[serde(deserialize_with = "lenient")]
     pub isPeloton: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl Groups {
@@ -181,24 +318,45 @@ impl Groups {
             timeGap1: 0,
             timeGap2: 0,
             isPeloton: false,
+            extra: Extra::new(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for Groups {
+    fn default() -> Self {
+        Groups::new()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 #[allow(dead_code, non_snake_case)]
 pub struct ResultsIndv {
+    #[serde(deserialize_with = "lenient")]
     pub location: u32,
+    #[serde(deserialize_with = "lenient")]
     pub position: u32,
+    #[serde(deserialize_with = "lenient")]
     pub name: String,
+    #[serde(deserialize_with = "lenient")]
     pub country: String,
+    #[serde(deserialize_with = "lenient")]
     pub team: String,
+    #[serde(deserialize_with = "lenient")]
     pub teamCode: String,
+    #[serde(deserialize_with = "lenient")]
     pub points: u32,
+    #[serde(deserialize_with = "lenient")]
     pub pointsTotal: u32,
+    #[serde(deserialize_with = "lenient")]
     pub time: u32,
+    #[serde(deserialize_with = "lenient")]
     pub deltaTime: i32,
+    #[serde(deserialize_with = "lenient")]
     pub isEliminated: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl ResultsIndv {
@@ -215,20 +373,37 @@ impl ResultsIndv {
             time: 0,
             deltaTime: 0,
             isEliminated: false,        
+            extra: Extra::new(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for ResultsIndv {
+    fn default() -> Self {
+        ResultsIndv::new()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 #[allow(dead_code, non_snake_case)]
 pub struct ResultsTeam {
+    #[serde(deserialize_with = "lenient")]
     pub location: u32,
+    #[serde(deserialize_with = "lenient")]
     pub position: u32,
+    #[serde(deserialize_with = "lenient")]
     pub team: String,
+    #[serde(deserialize_with = "lenient")]
     pub teamCode: String,
+    #[serde(deserialize_with = "lenient")]
     pub pointsTotal: u32,
+    #[serde(deserialize_with = "lenient")]
     pub time: f32,
+    #[serde(deserialize_with = "lenient")]
     pub deltaTime: f32,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl ResultsTeam {
@@ -241,15 +416,82 @@ impl ResultsTeam {
             pointsTotal: 0,
             time: 0.0,
             deltaTime: 0.0,
+            extra: Extra::new(),
         }
     }
 }
 
-#[derive(Clone, PartialEq)]
+impl Default for ResultsTeam {
+    fn default() -> Self {
+        ResultsTeam::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum BcastStatus {
     Unknown,
     Ok,
     NotOk,
+    // data was received, but could not be deserialized
+    ParseError,
+}
+
+// reason why data could not be retrieved from the source
+#[derive(Clone, Debug, PartialEq)]
+pub enum BcastError {
+    ConnectionRefused,
+    Timeout,
+    HttpStatus(u16),
+    EmptyBody,
+    FileNotFound(String),
+    Other(String),
+}
+
+impl fmt::Display for BcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BcastError::ConnectionRefused => write!(f, "Connection refused (is the source running and reachable?)"),
+            BcastError::Timeout => write!(f, "Request timed out"),
+            BcastError::HttpStatus(code) => write!(f, "Unexpected HTTP status {}", code),
+            BcastError::EmptyBody => write!(f, "Empty response"),
+            BcastError::FileNotFound(path) => write!(f, "{} not found (is the bcast directory right and TPV running?)", path),
+            BcastError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// When a frame was received, both in ms since the UNIX epoch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Received {
+    /// Wall-clock time of this host
+    pub local: u64,
+    /// Time the source tells, e.g. the modification time of a bcast file, if known
+    pub source: Option<u64>,
+}
+
+impl Received {
+    /// Received just now.
+    pub fn now(source: Option<u64>) -> Received {
+        Received {
+            local: recorder::now_ms(),
+            source,
+        }
+    }
+}
+
+// samples kept for the diagnostics, about five minutes at 1 Hz
+const HISTORY_SIZE: usize = 300;
+
+/// Health of a stream at the time of an update or error.
+#[derive(Clone, Debug)]
+pub struct BcastSample {
+    // ms since epoch
+    pub timestamp: u64,
+    // updates per second
+    pub rate: f32,
+    // ms
+    pub latency: Option<u32>,
+    pub errors: u64,
 }
 
 #[derive(Clone)]
@@ -258,6 +500,35 @@ pub struct BcastState {
     pub stopped: bool,
     pub status: BcastStatus,
     pub frame: u64,
+    pub parse_error: Option<String>,
+    pub parse_error_body: Option<String>,
+    pub schema: Option<SchemaInfo>,
+    pub last_error: Option<BcastError>,
+    // when the current data was received
+    pub received: Option<Received>,
+    // ms since epoch of the last update with new data
+    pub last_update: Option<u64>,
+    // ms since epoch of the last response, new data or not
+    pub last_seen: Option<u64>,
+    // updates per second, smoothed
+    pub rate: f32,
+    // round-trip time of the last request in ms, only known for HTTP sources
+    pub latency: Option<u32>,
+    pub errors: u64,
+    pub parse_errors: u64,
+    // text of the last error of any kind, kept when the stream recovers
+    pub last_error_text: Option<String>,
+    pub history: VecDeque<BcastSample>,
+    // hash of the last body, to tell if it changed
+    last_hash: Option<u64>,
+    notify: Option<Notify>,
+}
+
+// where status changes of a stream are told to
+#[derive(Clone)]
+struct Notify {
+    stream: &'static str,
+    events: Events,
 }
 
 impl BcastState {
@@ -267,14 +538,122 @@ impl BcastState {
             stopped: true,
             status: BcastStatus::Unknown,
             frame: 0,
+            parse_error: None,
+            parse_error_body: None,
+            schema: None,
+            last_error: None,
+            received: None,
+            last_update: None,
+            last_seen: None,
+            rate: 0.0,
+            latency: None,
+            errors: 0,
+            parse_errors: 0,
+            last_error_text: None,
+            history: VecDeque::new(),
+            last_hash: None,
+            notify: None,
+        }
+    }
+
+    /// State which emits a notification whenever the status changes.
+    pub(crate) fn with_events(stream: &'static str, events: &Events) -> BcastState {
+        BcastState {
+            notify: Some(Notify {
+                stream,
+                events: events.clone(),
+            }),
+            ..BcastState::new()
+        }
+    }
+
+    fn set_status(&mut self, status: BcastStatus) {
+        if self.status == status {
+            return;
+        }
+
+        self.status = status.clone();
+
+        if let Some(n) = &self.notify {
+            n.events.emit(Notification::Status { source: n.events.source(), stream: n.stream, status });
+        }
+    }
+
+    /// True if the stream is running, but no new data arrived for `after` ms.
+    /// The source may still be reachable, see `last_seen`.
+    pub fn stale(&self, after: u64) -> bool {
+        if !self.started {
+            return false;
+        }
+
+        let since = self.last_update.or(self.history.front().map(|s| s.timestamp));
+
+        match since {
+            Some(t) => recorder::now_ms().saturating_sub(t) > after,
+            None => false,
+        }
+    }
+
+    // forget everything measured in a previous run
+    fn reset_diagnostics(&mut self) {
+        self.last_update = None;
+        self.last_seen = None;
+        self.last_hash = None;
+        self.rate = 0.0;
+        self.latency = None;
+        self.errors = 0;
+        self.parse_errors = 0;
+        self.last_error_text = None;
+        self.history.clear();
+    }
+
+    fn updated(&mut self) {
+        let now = recorder::now_ms();
+
+        if let Some(last) = self.last_update {
+            let rate = 1000.0 / now.saturating_sub(last).max(1) as f32;
+
+            // exponential moving average, the first update is taken as is
+            self.rate = if self.rate > 0.0 { self.rate * 0.8 + rate * 0.2 } else { rate };
+        }
+        self.last_update = Some(now);
+        self.last_seen = Some(now);
+        self.sample(now);
+    }
+
+    fn failed(&mut self, error: String) {
+        self.last_error_text = Some(error);
+        self.sample(recorder::now_ms());
+    }
+
+    fn sample(&mut self, timestamp: u64) {
+        if self.history.len() >= HISTORY_SIZE {
+            self.history.pop_front();
         }
+        self.history.push_back(BcastSample {
+            timestamp,
+            rate: self.rate,
+            latency: self.latency,
+            errors: self.errors + self.parse_errors,
+        });
+    }
+}
+
+impl Default for BcastState {
+    fn default() -> Self {
+        BcastState::new()
     }
 }
 
-pub trait BcastStreamBase {
+// some helpers are only used by the workers of the optional sources
+#[allow(dead_code)]
+pub(crate) trait BcastStreamBase {
     fn set_started_t(state: &Arc<Mutex<BcastState>>, started: bool) {
         let mut state_locked = state.lock().unwrap();
 
+        if started && !state_locked.started {
+            state_locked.reset_diagnostics();
+        }
         state_locked.started = started;
         state_locked.stopped = !started;
     }
@@ -290,9 +669,92 @@ pub trait BcastStreamBase {
        
         if new_state == BcastStatus::Ok {
             state_locked.frame += 1;
+            state_locked.updated();
         }
        
-        state_locked.status = new_state;
+        state_locked.set_status(new_state);
+    }
+
+    fn set_parse_error_t(state: &Arc<Mutex<BcastState>>, error: String, body: &str) {
+        let mut state_locked = state.lock().unwrap();
+
+        state_locked.set_status(BcastStatus::ParseError);
+        state_locked.parse_errors += 1;
+        state_locked.failed(error.clone());
+        state_locked.parse_error = Some(error);
+        state_locked.parse_error_body = Some(String::from(body));
+    }
+
+    // sleep, but wake up early if the stream gets stopped
+    fn sleep_t(state: &Arc<Mutex<BcastState>>, duration: time::Duration) {
+        let until = time::Instant::now() + duration;
+
+        loop {
+            let now = time::Instant::now();
+
+            if now >= until || !Self::started_t(state) {
+                break;
+            }
+            thread::sleep((until - now).min(time::Duration::from_millis(100)));
+        }
+    }
+
+    fn set_error_t(state: &Arc<Mutex<BcastState>>, error: BcastError) {
+        let mut state_locked = state.lock().unwrap();
+
+        state_locked.set_status(BcastStatus::NotOk);
+        state_locked.errors += 1;
+        state_locked.failed(error.to_string());
+        state_locked.last_error = Some(error);
+    }
+
+    /// True if `body` is the same as last time, so there is nothing new to
+    /// parse or pass on. A source which recovered is back to `Ok` though.
+    fn unchanged_t(state: &Arc<Mutex<BcastState>>, body: &str) -> bool {
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let mut state_locked = state.lock().unwrap();
+
+        if state_locked.last_hash != Some(hash) {
+            state_locked.last_hash = Some(hash);
+            return false;
+        }
+
+        state_locked.last_seen = Some(recorder::now_ms());
+
+        if state_locked.status == BcastStatus::NotOk {
+            state_locked.set_status(BcastStatus::Ok);
+        }
+        true
+    }
+
+    fn set_received_t(state: &Arc<Mutex<BcastState>>, received: Received) {
+        let mut state_locked = state.lock().unwrap();
+        state_locked.received = Some(received);
+    }
+
+    fn set_latency_t(state: &Arc<Mutex<BcastState>>, latency: time::Duration) {
+        let mut state_locked = state.lock().unwrap();
+        state_locked.latency = Some(latency.as_millis().min(u32::MAX as u128) as u32);
+    }
+
+    fn set_schema_t(state: &Arc<Mutex<BcastState>>, schema: Option<SchemaInfo>) {
+        // empty frames tell nothing about the schema, keep what we know
+        let schema = match schema {
+            Some(s) => s,
+            None => return,
+        };
+
+        let mut state_locked = state.lock().unwrap();
+
+        if state_locked.schema.as_ref() != Some(&schema) {
+            if schema.version.is_none() {
+                log::warn!("{}", schema.describe());
+            }
+            state_locked.schema = Some(schema);
+        }
     }
 
 
@@ -303,10 +765,13 @@ pub trait BcastStreamBase {
     fn stopped(&self) -> bool;
 }
 
-pub struct BcastStreamFocus {
+#[derive(Clone)]
+pub(crate) struct BcastStreamFocus {
     state: Arc<Mutex<BcastState>>,
     data: Arc<Mutex<Focus>>,
-    ride: Arc<Mutex<Ride>>,
+    // shared with the readers, so they do not need a copy of the whole ride,
+    // it is only copied if a reader still holds it while it changes
+    ride: Arc<Mutex<Arc<Ride>>>,
 }
 
 impl BcastStreamBase for BcastStreamFocus {
@@ -325,11 +790,11 @@ impl BcastStreamBase for BcastStreamFocus {
 }
 
 impl BcastStreamFocus {
-    pub fn new() -> BcastStreamFocus {
+    pub fn new(events: &Events) -> BcastStreamFocus {
         BcastStreamFocus {
-            state: Arc::new(Mutex::new(BcastState::new())),
+            state: Arc::new(Mutex::new(BcastState::with_events("focus", events))),
             data: Arc::new(Mutex::new(Focus::new())),
-            ride: Arc::new(Mutex::new(Ride::new())),
+            ride: Arc::new(Mutex::new(Arc::new(Ride::new()))),
         }
     }
 
@@ -343,13 +808,26 @@ impl BcastStreamFocus {
         state_locked.clone()
     }
 
-    pub fn ride(&self) -> Ride {
+    pub fn ride(&self) -> Arc<Ride> {
         let ride_locked = self.ride.lock().unwrap();
-        ride_locked.clone()
+        Arc::clone(&ride_locked)
+    }
+
+    pub fn lap(&self) {
+        Arc::make_mut(&mut self.ride.lock().unwrap()).lap();
+    }
+
+    pub fn set_auto_lap(&self, auto_lap: AutoLap) {
+        Arc::make_mut(&mut self.ride.lock().unwrap()).auto_lap = auto_lap;
+    }
+
+    pub fn set_athlete(&self, athlete: Athlete) {
+        Arc::make_mut(&mut self.ride.lock().unwrap()).set_athlete(athlete);
     }
 }
 
-pub struct BcastStreamNearest {
+#[derive(Clone)]
+pub(crate) struct BcastStreamNearest {
     state: Arc<Mutex<BcastState>>,
     data: Arc<Mutex<Vec<Nearest>>>,
 }
@@ -370,9 +848,9 @@ impl BcastStreamBase for BcastStreamNearest {
 }
 
 impl BcastStreamNearest {
-    pub fn new() -> BcastStreamNearest {
+    pub fn new(events: &Events) -> BcastStreamNearest {
         BcastStreamNearest {
-            state: Arc::new(Mutex::new(BcastState::new())),
+            state: Arc::new(Mutex::new(BcastState::with_events("nearest", events))),
             data: Arc::new(Mutex::new(vec![Nearest::new()])),
         }
     }
@@ -388,7 +866,8 @@ impl BcastStreamNearest {
     }
 }
 
-pub struct BcastStreamEvent {
+#[derive(Clone)]
+pub(crate) struct BcastStreamEvent {
     state: Arc<Mutex<BcastState>>,
     data: Arc<Mutex<Event>>,
 }
@@ -409,9 +888,9 @@ impl BcastStreamBase for BcastStreamEvent {
 }
 
 impl BcastStreamEvent {
-    pub fn new() -> BcastStreamEvent {
+    pub fn new(events: &Events) -> BcastStreamEvent {
         BcastStreamEvent {
-            state: Arc::new(Mutex::new(BcastState::new())),
+            state: Arc::new(Mutex::new(BcastState::with_events("event", events))),
             data: Arc::new(Mutex::new(Event::new())),
         }
     }
@@ -427,7 +906,8 @@ impl BcastStreamEvent {
     }
 }
 
-pub struct BcastStreamEntries {
+#[derive(Clone)]
+pub(crate) struct BcastStreamEntries {
     state: Arc<Mutex<BcastState>>,
     data: Arc<Mutex<Vec<Entries>>>,
 }
@@ -448,9 +928,9 @@ impl BcastStreamBase for BcastStreamEntries {
 }
 
 impl BcastStreamEntries {
-    pub fn new() -> BcastStreamEntries {
+    pub fn new(events: &Events) -> BcastStreamEntries {
         BcastStreamEntries {
-            state: Arc::new(Mutex::new(BcastState::new())),
+            state: Arc::new(Mutex::new(BcastState::with_events("entries", events))),
             data: Arc::new(Mutex::new(vec![Entries::new()])),
         }
     }
@@ -466,7 +946,8 @@ impl BcastStreamEntries {
     }
 }
 
-pub struct BcastStreamGroups {
+#[derive(Clone)]
+pub(crate) struct BcastStreamGroups {
     state: Arc<Mutex<BcastState>>,
     data: Arc<Mutex<Vec<Groups>>>,
 }
@@ -487,9 +968,9 @@ impl BcastStreamBase for BcastStreamGroups {
 }
 
 impl BcastStreamGroups {
-    pub fn new() -> BcastStreamGroups {
+    pub fn new(events: &Events) -> BcastStreamGroups {
         BcastStreamGroups {
-            state: Arc::new(Mutex::new(BcastState::new())),
+            state: Arc::new(Mutex::new(BcastState::with_events("groups", events))),
             data: Arc::new(Mutex::new(vec![Groups::new()])),
         }
     }
@@ -505,7 +986,8 @@ impl BcastStreamGroups {
     }
 }
 
-pub struct BcastStreamResultsIndv {
+#[derive(Clone)]
+pub(crate) struct BcastStreamResultsIndv {
     state: Arc<Mutex<BcastState>>,
     data: Arc<Mutex<Vec<ResultsIndv>>>,
 }
@@ -526,9 +1008,9 @@ impl BcastStreamBase for BcastStreamResultsIndv {
 }
 
 impl BcastStreamResultsIndv {
-    pub fn new() -> BcastStreamResultsIndv {
+    pub fn new(events: &Events) -> BcastStreamResultsIndv {
         BcastStreamResultsIndv {
-            state: Arc::new(Mutex::new(BcastState::new())),
+            state: Arc::new(Mutex::new(BcastState::with_events("resultsIndv", events))),
             data: Arc::new(Mutex::new(vec![ResultsIndv::new()])),
         }
     }
@@ -544,7 +1026,8 @@ impl BcastStreamResultsIndv {
     }
 }
 
-pub struct BcastStreamResultsTeam {
+#[derive(Clone)]
+pub(crate) struct BcastStreamResultsTeam {
     state: Arc<Mutex<BcastState>>,
     data: Arc<Mutex<Vec<ResultsTeam>>>,
 }
@@ -565,9 +1048,9 @@ impl BcastStreamBase for BcastStreamResultsTeam {
 }
 
 impl BcastStreamResultsTeam {
-    pub fn new() -> BcastStreamResultsTeam {
+    pub fn new(events: &Events) -> BcastStreamResultsTeam {
         BcastStreamResultsTeam {
-            state: Arc::new(Mutex::new(BcastState::new())),
+            state: Arc::new(Mutex::new(BcastState::with_events("resultsTeam", events))),
             data: Arc::new(Mutex::new(vec![ResultsTeam::new()])),
         }
     }
@@ -0,0 +1,174 @@
+#[cfg(feature = "http-source")]
+use std::time;
+
+use serde::{Deserialize, Serialize};
+
+/// Timing of a polling source. All times are in ms.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BcastConfig {
+    // poll interval per stream
+    pub focus: u64,
+    pub nearest: u64,
+    pub event: u64,
+    pub entries: u64,
+    pub groups: u64,
+    pub results_indv: u64,
+    pub results_team: u64,
+    // delay after the first failed request, doubled on every further failure up to the ceiling
+    pub backoff_min: u64,
+    pub backoff_max: u64,
+    // random variation of all delays in percent, 0 to disable
+    pub jitter: u32,
+    // time to wait for a response
+    pub timeout: u64,
+    // a running stream without new data for this long is considered stale
+    pub stale: u64,
+}
+
+impl BcastConfig {
+    pub fn new() -> BcastConfig {
+        BcastConfig {
+            focus: 250,
+            nearest: 1000,
+            event: 1000,
+            entries: 1000,
+            groups: 1000,
+            results_indv: 1000,
+            results_team: 1000,
+            backoff_min: 1000,
+            backoff_max: 30000,
+            jitter: 0,
+            timeout: 2000,
+            stale: 5000,
+        }
+    }
+}
+
+impl Default for BcastConfig {
+    fn default() -> Self {
+        BcastConfig::new()
+    }
+}
+
+/// Serving the bcast data to other TPVUI instances, see `Facade::set_server_config`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub enabled: bool,
+    pub bind: String,
+    pub port: u16,
+    // comma separated list of addresses (e.g. 192.168.1.10) or networks (e.g. 192.168.1.0/24)
+    // allowed to connect, empty to allow everyone
+    pub allow: String,
+    // answer discovery queries, so clients find this server
+    pub announce: bool,
+    // name clients see when searching, the host name if empty
+    pub name: String,
+}
+
+impl ServerConfig {
+    pub fn new() -> ServerConfig {
+        ServerConfig {
+            enabled: false,
+            bind: String::from("0.0.0.0"),
+            port: 8080,
+            allow: String::new(),
+            announce: true,
+            name: String::new(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig::new()
+    }
+}
+
+/// Pushing frames and ride to other tools, see `Facade::set_push_config`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PushConfig {
+    pub enabled: bool,
+    pub bind: String,
+    pub port: u16,
+}
+
+impl PushConfig {
+    pub fn new() -> PushConfig {
+        PushConfig {
+            enabled: false,
+            bind: String::from("127.0.0.1"),
+            port: 8081,
+        }
+    }
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        PushConfig::new()
+    }
+}
+
+#[cfg(feature = "http-source")]
+/// Tells a worker how long to wait before the next request.
+pub struct Pacer {
+    interval: u64,
+    backoff_min: u64,
+    backoff_max: u64,
+    jitter: u32,
+    failures: u32,
+    // xorshift state for the jitter
+    seed: u64,
+}
+
+#[cfg(feature = "http-source")]
+impl Pacer {
+    pub fn new(interval: u64, config: &BcastConfig) -> Pacer {
+        let nanos = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u64)
+            .unwrap_or(0);
+
+        Pacer {
+            interval,
+            backoff_min: config.backoff_min.max(1),
+            backoff_max: config.backoff_max.max(config.backoff_min.max(1)),
+            jitter: config.jitter.min(100),
+            failures: 0,
+            // xorshift must not be seeded with 0
+            seed: (nanos ^ interval.rotate_left(32)) | 1,
+        }
+    }
+
+    /// Delay after a successful request.
+    pub fn ok(&mut self) -> time::Duration {
+        self.failures = 0;
+        self.jittered(self.interval)
+    }
+
+    /// Delay after a failed request.
+    pub fn failed(&mut self) -> time::Duration {
+        let delay = self.backoff_min.saturating_mul(1 << self.failures.min(16)).min(self.backoff_max);
+
+        self.failures = self.failures.saturating_add(1);
+        self.jittered(delay)
+    }
+
+    fn jittered(&mut self, ms: u64) -> time::Duration {
+        if self.jitter == 0 {
+            return time::Duration::from_millis(ms);
+        }
+
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+
+        // -1.0 .. 1.0
+        let r = (self.seed % 2001) as f64 / 1000.0 - 1.0;
+        let ms = ms as f64 * (1.0 + r * self.jitter as f64 / 100.0);
+
+        time::Duration::from_millis(ms.max(0.0) as u64)
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "gui")]
+use std::{collections::HashMap, fs, io, path::Path};
+
+#[cfg(feature = "http-source")]
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// How to authenticate at an HTTP source.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum Auth {
+    #[default]
+    None,
+    Basic { user: String, password: String },
+    Bearer { token: String },
+}
+
+#[cfg(feature = "http-source")]
+impl Auth {
+    /// Value of the `Authorization` header, if any.
+    pub fn header(&self) -> Option<String> {
+        match self {
+            Auth::None => None,
+            Auth::Basic { user, password } => Some(format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password))
+            )),
+            Auth::Bearer { token } => Some(format!("Bearer {}", token.trim())),
+        }
+    }
+}
+
+/// Everything needed to access a protected HTTP source. These are kept out of
+/// the persisted app state, see `load` and `save`. Passwords and tokens are
+/// stored as plain text, only protected by the permissions of the file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Credentials {
+    pub auth: Auth,
+    // PEM file with a certificate to trust in addition to the well known CAs,
+    // e.g. the self-signed one of a reverse proxy
+    pub trust: String,
+}
+
+#[cfg(feature = "gui")]
+/// Read the credentials of all sources (by name), nothing if there is no file yet.
+pub fn load(path: &Path) -> io::Result<HashMap<String, Credentials>> {
+    match fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(feature = "gui")]
+/// Write the credentials of all sources (by name) as plain text, readable by
+/// the user only. The permissions are also fixed for an existing file.
+pub fn save(path: &Path, credentials: &HashMap<String, Credentials>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(credentials).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+
+    // the mode only applies to a new file, it could have been created otherwise
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+
+    io::Write::write_all(&mut file, json.as_bytes())
+}
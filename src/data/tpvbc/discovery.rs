@@ -0,0 +1,231 @@
+#[cfg(feature = "gui")]
+use std::{collections::HashSet, net::SocketAddr};
+use std::{
+    net::{Ipv4Addr, UdpSocket},
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+    thread,
+    time,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// UDP port servers listen on for discovery queries.
+pub const DISCOVERY_PORT: u16 = 8099;
+
+// what a client broadcasts to find servers
+const QUERY: &[u8] = b"TPVBC-DISCOVER";
+
+// service name in the answers, other services may use the same port
+const SERVICE: &str = "tpvbc";
+
+#[cfg(feature = "gui")]
+// how long to collect answers to a query
+const SEARCH_TIME: time::Duration = time::Duration::from_millis(1500);
+
+/// Answer of a server to a discovery query. The address is the one the
+/// answer came from, so only the port is sent.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Beacon {
+    service: String,
+    name: String,
+    port: u16,
+}
+
+#[cfg(feature = "gui")]
+/// A bcast server found on the network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Found {
+    pub name: String,
+    pub url: String,
+}
+
+#[cfg(feature = "serve")]
+// name to announce if none is configured. There is no portable way to get the
+// host name without another crate: Windows sets COMPUTERNAME, Linux has it in
+// /etc/hostname or /proc, HOSTNAME is a shell variable often not exported.
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME").ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|n| String::from(n.trim()))
+        .filter(|n| !n.is_empty())
+        .unwrap_or(String::from("tpvui"))
+}
+
+#[cfg(feature = "serve")]
+/// Answers discovery queries while bcast data is served, so clients on the
+/// network could find this instance. Cloning an `Announcer` gives another
+/// handle to the same announcer.
+#[derive(Clone)]
+pub struct Announcer {
+    running: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+}
+
+#[cfg(feature = "serve")]
+impl Announcer {
+    pub fn new() -> Announcer {
+        Announcer {
+            running: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Announce the server listening on `port` as `name`, the host name if empty.
+    pub fn start(&self, port: u16, name: &str) -> Result<(), String> {
+        let mut running_locked = self.running.lock().unwrap();
+
+        if running_locked.is_some() {
+            return Ok(());
+        }
+
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))
+            .map_err(|e| format!("Failed to listen for discovery queries on port {}: {}", DISCOVERY_PORT, e))?;
+
+        socket.set_read_timeout(Some(time::Duration::from_millis(100))).map_err(|e| e.to_string())?;
+
+        let beacon = serde_json::to_vec(&Beacon {
+            service: String::from(SERVICE),
+            name: if name.trim().is_empty() { host_name() } else { String::from(name.trim()) },
+            port,
+        })
+        .map_err(|e| e.to_string())?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        *running_locked = Some(Arc::clone(&running));
+
+        log::info!("Announcing bcast server on port {} to discovery queries", port);
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 64];
+
+            while running.load(Ordering::Relaxed) {
+                match socket.recv_from(&mut buf) {
+                    Ok((n, from)) if &buf[..n] == QUERY => {
+                        log::debug!("Discovery query from {}", from);
+                        if let Err(e) = socket.send_to(&beacon, from) {
+                            log::debug!("Failed to answer discovery query from {}: {}", from, e);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            log::info!("Announcing stopped");
+        });
+
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        if let Some(running) = self.running.lock().unwrap().take() {
+            running.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(feature = "serve")]
+impl Default for Announcer {
+    fn default() -> Self {
+        Announcer::new()
+    }
+}
+
+#[cfg(feature = "gui")]
+/// Searches the local network for bcast servers in the background.
+#[derive(Clone)]
+pub struct Discovery {
+    searching: Arc<AtomicBool>,
+    found: Arc<Mutex<Vec<Found>>>,
+}
+
+#[cfg(feature = "gui")]
+impl Discovery {
+    pub fn new() -> Discovery {
+        Discovery {
+            searching: Arc::new(AtomicBool::new(false)),
+            found: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Start a new search, servers found before are forgotten.
+    pub fn search(&self) {
+        if self.searching.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        self.found.lock().unwrap().clear();
+
+        let searching = Arc::clone(&self.searching);
+        let found = Arc::clone(&self.found);
+
+        thread::spawn(move || {
+            if let Err(e) = Discovery::query(&found) {
+                log::warn!("Failed to search for bcast servers: {}", e);
+            }
+            searching.store(false, Ordering::Relaxed);
+        });
+    }
+
+    pub fn searching(&self) -> bool {
+        self.searching.load(Ordering::Relaxed)
+    }
+
+    pub fn found(&self) -> Vec<Found> {
+        self.found.lock().unwrap().clone()
+    }
+
+    fn query(found: &Arc<Mutex<Vec<Found>>>) -> std::io::Result<()> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+
+        socket.set_broadcast(true)?;
+        socket.set_read_timeout(Some(time::Duration::from_millis(100)))?;
+
+        // broadcasts are not always looped back, ask this host explicitly
+        for addr in [Ipv4Addr::BROADCAST, Ipv4Addr::LOCALHOST] {
+            if let Err(e) = socket.send_to(QUERY, (addr, DISCOVERY_PORT)) {
+                log::debug!("Failed to send discovery query to {}: {}", addr, e);
+            }
+        }
+
+        let until = time::Instant::now() + SEARCH_TIME;
+        let mut seen = HashSet::new();
+        let mut buf = [0u8; 512];
+
+        while time::Instant::now() < until {
+            let (n, from) = match socket.recv_from(&mut buf) {
+                Ok(r) => r,
+                Err(_) => continue,
+            };
+
+            let beacon = match serde_json::from_slice::<Beacon>(&buf[..n]) {
+                Ok(b) if b.service == SERVICE => b,
+                Ok(_) => continue,
+                Err(e) => {
+                    log::debug!("Ignoring invalid discovery answer from {}: {}", from, e);
+                    continue;
+                }
+            };
+
+            // the same server answers the broadcast and the query to localhost
+            let addr = match from {
+                SocketAddr::V4(a) if a.ip().is_loopback() => String::from("localhost"),
+                _ => from.ip().to_string(),
+            };
+
+            if seen.insert((beacon.name.clone(), beacon.port)) {
+                let url = format!("http://{}:{}", addr, beacon.port);
+
+                log::info!("Found bcast server '{}' on {}", beacon.name, url);
+                found.lock().unwrap().push(Found { name: beacon.name, url });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl Default for Discovery {
+    fn default() -> Self {
+        Discovery::new()
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::{mpsc, Arc, Mutex};
+
+use crate::data::ride::Ride;
+
+use super::{BcastStatus, Focus, Received, ResultsIndv, ResultsTeam};
+
+/// What a subscriber gets told about. `source` is the index of the source in
+/// `Facade::sources()` the notification is about.
+#[derive(Clone)]
+#[allow(dead_code)]
+pub enum Notification {
+    /// A frame with new data was received for a stream (named like the TPV bcast file)
+    Frame { source: usize, stream: String, body: String, received: Received },
+    /// A new focus frame was received (the ride is already updated)
+    Focus { source: usize, focus: Focus },
+    /// New individual results were received
+    ResultsIndv { source: usize, results: Vec<ResultsIndv> },
+    /// New team results were received
+    ResultsTeam { source: usize, results: Vec<ResultsTeam> },
+    /// The status of a stream (named like the TPV bcast file) changed
+    Status { source: usize, stream: &'static str, status: BcastStatus },
+    /// The given lap (counting from 1) was completed
+    LapCompleted { source: usize, lap: u32 },
+    /// The ride data was reset, e.g. because a new ride was started in TPV
+    RideReset { source: usize },
+}
+
+/// The kinds of notifications, to subscribe to some of them only.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NotificationKind {
+    Frame,
+    Focus,
+    ResultsIndv,
+    ResultsTeam,
+    Status,
+    LapCompleted,
+    RideReset,
+}
+
+impl Notification {
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            Notification::Frame { .. } => NotificationKind::Frame,
+            Notification::Focus { .. } => NotificationKind::Focus,
+            Notification::ResultsIndv { .. } => NotificationKind::ResultsIndv,
+            Notification::ResultsTeam { .. } => NotificationKind::ResultsTeam,
+            Notification::Status { .. } => NotificationKind::Status,
+            Notification::LapCompleted { .. } => NotificationKind::LapCompleted,
+            Notification::RideReset { .. } => NotificationKind::RideReset,
+        }
+    }
+}
+
+struct Subscriber {
+    tx: mpsc::Sender<Notification>,
+    // none for all kinds
+    kinds: Option<Vec<NotificationKind>>,
+}
+
+impl Subscriber {
+    fn wants(&self, kind: NotificationKind) -> bool {
+        match &self.kinds {
+            Some(kinds) => kinds.contains(&kind),
+            None => true,
+        }
+    }
+}
+
+// what is needed to tell what happened to the ride
+struct RideSeen {
+    time: u32,
+    lap: u32,
+}
+
+/// Delivers notifications to everyone who subscribed. Cloning `Events` gives
+/// another handle to the same subscribers.
+#[derive(Clone)]
+pub struct Events {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    // index of the source told about
+    source: usize,
+    ride: Arc<Mutex<RideSeen>>,
+}
+
+impl Events {
+    pub fn new() -> Events {
+        Events {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            source: 0,
+            ride: Arc::new(Mutex::new(RideSeen { time: 0, lap: 0 })),
+        }
+    }
+
+    /// Another handle to the same subscribers for the source with the given
+    /// index, which keeps track of another ride.
+    pub fn fork(&self, source: usize) -> Events {
+        Events {
+            subscribers: Arc::clone(&self.subscribers),
+            source,
+            ride: Arc::new(Mutex::new(RideSeen { time: 0, lap: 0 })),
+        }
+    }
+
+    /// Get all further notifications. The channel is unbounded, so the
+    /// receiver must be drained regularly or dropped to unsubscribe.
+    pub fn subscribe(&self) -> mpsc::Receiver<Notification> {
+        self.subscribe_with(None)
+    }
+
+    /// Like `subscribe`, but only get notifications of the given kinds. The
+    /// others are not even built for this subscriber.
+    pub fn subscribe_to(&self, kinds: &[NotificationKind]) -> mpsc::Receiver<Notification> {
+        self.subscribe_with(Some(kinds.to_vec()))
+    }
+
+    fn subscribe_with(&self, kinds: Option<Vec<NotificationKind>>) -> mpsc::Receiver<Notification> {
+        let (tx, rx) = mpsc::channel();
+
+        self.subscribers.lock().unwrap().push(Subscriber { tx, kinds });
+        rx
+    }
+
+    /// Index of the source the notifications are about.
+    pub fn source(&self) -> usize {
+        self.source
+    }
+
+    pub fn subscribed(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    pub fn emit(&self, notification: Notification) {
+        self.emit_with(notification.kind(), || notification);
+    }
+
+    /// Like `emit`, but the notification is only built if somebody subscribed
+    /// to its `kind`.
+    pub fn emit_with<F: FnOnce() -> Notification>(&self, kind: NotificationKind, notification: F) {
+        let mut subscribers_locked = self.subscribers.lock().unwrap();
+
+        if !subscribers_locked.iter().any(|s| s.wants(kind)) {
+            return;
+        }
+
+        let notification = notification();
+
+        // receivers which were dropped are unsubscribed
+        subscribers_locked.retain(|s| !s.wants(kind) || s.tx.send(notification.clone()).is_ok());
+    }
+
+    /// Tell about laps and resets by comparing the ride with the last one seen.
+    pub fn ride(&self, ride: &Ride) {
+        let mut seen_locked = self.ride.lock().unwrap();
+
+        if ride.total.time < seen_locked.time {
+            self.emit(Notification::RideReset { source: self.source });
+        } else if ride.total.lap > seen_locked.lap && seen_locked.lap > 0 {
+            for lap in seen_locked.lap..ride.total.lap {
+                self.emit(Notification::LapCompleted { source: self.source, lap });
+            }
+        }
+
+        seen_locked.time = ride.total.time;
+        seen_locked.lap = ride.total.lap;
+    }
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Events::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_only_get_the_kinds_wanted() {
+        let events = Events::new();
+        let all = events.subscribe();
+        let resets = events.subscribe_to(&[NotificationKind::RideReset]);
+
+        events.emit(Notification::LapCompleted { source: 0, lap: 1 });
+        events.emit(Notification::RideReset { source: 0 });
+
+        assert_eq!(all.try_iter().map(|n| n.kind()).collect::<Vec<_>>(), vec![NotificationKind::LapCompleted, NotificationKind::RideReset]);
+        assert_eq!(resets.try_iter().map(|n| n.kind()).collect::<Vec<_>>(), vec![NotificationKind::RideReset]);
+    }
+
+    #[test]
+    fn notifications_nobody_wants_are_not_built() {
+        let events = Events::new();
+        let _resets = events.subscribe_to(&[NotificationKind::RideReset]);
+
+        events.emit_with(NotificationKind::Frame, || panic!("frame built"));
+    }
+}
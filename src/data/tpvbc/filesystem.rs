@@ -1,10 +1,13 @@
 use unicode_bom::Bom;
-use std::{sync::Arc, path::Path, sync::mpsc, thread, time, fs};
+use std::{sync::{Arc, Mutex}, path::{Path, PathBuf}, sync::mpsc, thread, time, fs, io};
 
-use crate::data::ride::Ride;
+use crate::data::{athlete::Athlete, ride::{AutoLap, Ride}};
 
 use super::{
     interface::BcastStreamIf, 
+    schema,
+    tap::Tap,
+    BcastError, 
     BcastState, 
     BcastStatus, 
     BcastStreamBase, 
@@ -19,21 +22,159 @@ use super::{
     Event, 
     Focus, 
     Groups, 
-    Nearest, 
+    Nearest,
+    Received,
     ResultsIndv, 
     ResultsTeam
 };
 
+// how long to wait for a change before looking again
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(250);
+
+// TPV writes its files in place, reading while it writes gives incomplete JSON
+const READ_RETRIES: u32 = 3;
+const READ_RETRY_DELAY: time::Duration = time::Duration::from_millis(50);
+
+/// Tells when a file changed. The directory is watched, so the file may not
+/// exist yet or come and go. As native events do not work everywhere (e.g. on
+/// network shares), the modification time is checked as well.
+struct FileWatch {
+    path: PathBuf,
+    // None if native events are not available
+    events: Option<(notify::RecommendedWatcher, mpsc::Receiver<notify::Result<notify::Event>>)>,
+    // None until looked at the first time, so the first check tells a change
+    modified: Option<Option<time::SystemTime>>,
+    // of the worker, to stop waiting once it is stopped
+    state: Arc<Mutex<BcastState>>,
+}
+
+impl FileWatch {
+    fn new(path: &str, state: &Arc<Mutex<BcastState>>) -> FileWatch {
+        let path = PathBuf::from(path);
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+
+        let events = notify::recommended_watcher(tx)
+            .and_then(|mut w| notify::Watcher::watch(&mut w, &dir, notify::RecursiveMode::NonRecursive).map(|_| w));
+
+        let events = match events {
+            Ok(w) => {
+                log::info!("Watching {} for {:?}", dir.display(), path.file_name().unwrap_or_default());
+                Some((w, rx))
+            }
+            Err(e) => {
+                log::warn!("Failed to watch {}, polling instead: {}", dir.display(), e);
+                None
+            }
+        };
+
+        FileWatch {
+            path,
+            events,
+            modified: None,
+            state: Arc::clone(state),
+        }
+    }
+
+    /// Wait for a change of the file for a while.
+    fn changed(&mut self) -> bool {
+        let mut touched = false;
+
+        match &self.events {
+            Some((_, rx)) => match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(event) => {
+                    touched = self.concerns(event);
+
+                    // TPV writes in several steps, flush the follow up events. The
+                    // whole directory is watched and TPV keeps writing, so only
+                    // for a while.
+                    let until = time::Instant::now() + READ_RETRY_DELAY;
+
+                    while let Some(left) = until.checked_duration_since(time::Instant::now()) {
+                        if !self.state.lock().unwrap().started {
+                            break;
+                        }
+
+                        match rx.recv_timeout(left) {
+                            Ok(event) => touched |= self.concerns(event),
+                            Err(_) => break,
+                        }
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => (),
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    log::warn!("Watching {} stopped, polling instead", self.path.display());
+                    self.events = None;
+                }
+            },
+            None => thread::sleep(POLL_INTERVAL),
+        }
+
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        let changed = touched || self.modified != Some(modified);
+
+        self.modified = Some(modified);
+        changed
+    }
+
+    fn concerns(&self, event: notify::Result<notify::Event>) -> bool {
+        match event {
+            Ok(e) => e.paths.iter().any(|p| p.file_name() == self.path.file_name()),
+            Err(e) => {
+                log::warn!("Error while watching {}: {}", self.path.display(), e);
+                false
+            }
+        }
+    }
+}
+
+/// Read a file written by TPV, without the UTF-8 BOM. A read which looks like
+/// it caught TPV writing is retried.
+fn read(path: &str) -> Result<(String, Option<u64>), BcastError> {
+    let mut retries = READ_RETRIES;
+
+    loop {
+        // taken before reading, so a newer write is not attributed to the old content
+        let modified = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64);
+
+        let content = fs::read_to_string(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => BcastError::FileNotFound(String::from(path)),
+            _ => BcastError::Other(format!("Failed to read {}: {}", path, e)),
+        })?;
+
+        let content = String::from(&content[Bom::from(content.as_bytes()).len()..]);
+        let complete = serde_json::from_str::<serde::de::IgnoredAny>(&content).is_ok();
+
+        if complete || retries == 0 {
+            if content.trim().is_empty() {
+                return Err(BcastError::EmptyBody);
+            }
+            // if still incomplete, the parser tells what is wrong
+            return Ok((content, modified));
+        }
+
+        retries -= 1;
+        thread::sleep(READ_RETRY_DELAY);
+    }
+}
+
 pub struct BcastStreamFocusWorker {
     pub stream: BcastStreamFocus,
     pub url: String,
+    pub tap: Tap,
 }
 
 impl BcastStreamFocusWorker {
-    pub fn new() -> BcastStreamFocusWorker {
+    pub fn new(tap: Tap) -> BcastStreamFocusWorker {
         BcastStreamFocusWorker {
-            stream: BcastStreamFocus::new(),
+            stream: BcastStreamFocus::new(&tap.events),
             url: String::from("file:///home/stefan/devel/tpvbc2http/http/testing/focus.json"),
+            tap,
         }
     }
 
@@ -62,77 +203,66 @@ impl BcastStreamFocusWorker {
         let focus = Arc::clone(&self.stream.data);
         let ride = Arc::clone(&self.stream.ride);
         let url = self.url.clone();
+        let tap = self.tap.clone();
 
         thread::spawn(move || {
             <BcastStreamFocus as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'focus' started");
 
-            let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
-            let mut watcher = notify::recommended_watcher(tx).unwrap();
-    
-            log::info!("watching file: {}", url);
+            let mut watch = FileWatch::new(&url, &source);
 
-            // fixme: error handling
-            notify::Watcher::watch(&mut watcher, Path::new(&url), notify::RecursiveMode::NonRecursive).unwrap();
-            
             loop {
                 if !<BcastStreamFocus as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let changed: bool;
+                if watch.changed() {
+                    log::debug!("'focus' changed");
 
-                match rx.recv_timeout(time::Duration::from_millis(1000)) {
-                    Ok(_) => changed = true,
-                    Err(_) => changed = false,
-                }
-        
-                if changed {
-                    // flush all the follow up events ...
-                    loop {
-                        match rx.recv_timeout(time::Duration::from_millis(100)) {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    log::info!("'focus' changed");
-
-                    match fs::read_to_string(&url) {
-                        Ok(content) => (|c: &str| {
-                            let mut focus_list: Vec<Focus> = Vec::new();
-
-                            match serde_json::from_str(c) {
-                                Ok(obj) => focus_list = obj,
-                                Err(err) => (|e| {
-                                    log::warn!("Faild to deserialize 'focus' data: {}", e);
-                                })(err),
+                    match read(&url) {
+                        Ok((content, source_time)) => (|c: &str| {
+                            // rewritten with the same content
+                            if <BcastStreamFocus as BcastStreamBase>::unchanged_t(&source, c) {
+                                return;
                             }
-                            
-                            log::debug!("'focus' json:\n{focus_list:#?}");
-
-                            // all good, we got some data
-                            <BcastStreamFocus as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                            {
-                                let mut focus_locked = focus.lock().unwrap();
 
-                                match focus_list.get(0) {
-                                    Some(f) => *focus_locked = f.clone(),
-                                    None => (), // empty json array -> ignore
+                            let received = Received::now(source_time);
+                            tap.frame("focus", c, received);
+
+                            match schema::parse::<Focus>(c) {
+                                Ok((focus_list, schema)) => {
+                                    log::debug!("'focus' json:\n{focus_list:#?}");
+
+                                    // all good, we got some data
+                                    <BcastStreamFocus as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                    <BcastStreamFocus as BcastStreamBase>::set_received_t(&source, received);
+                                    <BcastStreamFocus as BcastStreamBase>::set_schema_t(&source, schema);
+                                    {
+                                        let mut focus_locked = focus.lock().unwrap();
+
+                                        // empty json array -> ignore
+                                        if let Some(f) = focus_list.first() {
+                                            *focus_locked = f.clone();
+                                        }
+
+                                        let mut ride_locked = ride.lock().unwrap();
+                                        Arc::make_mut(&mut ride_locked).update(focus_locked.clone(), received);
+                                        tap.focus(&focus_locked, &ride_locked);
+                                    }
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to deserialize 'focus' data: {}", err);
+                                    <BcastStreamFocus as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), c);
                                 }
-
-                                let mut ride_locked = ride.lock().unwrap();
-                                ride_locked.update(focus_locked.clone());
                             }
-                        })(&content[Bom::from(content.as_bytes()).len()..]), // remove utf-8 BOM if present
-                        Err(err) => (|e| {
-                            log::warn!("Failed to read 'focus': {}", e);
-                            <BcastStreamFocus as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                        })(err),
+                        })(&content),
+                        Err(err) => {
+                            log::warn!("Failed to read 'focus': {}", err);
+                            <BcastStreamFocus as BcastStreamBase>::set_error_t(&source, err);
+                        }
                     }
                 }
-                thread::sleep(time::Duration::from_millis(250));
             }
             log::info!("Worker thread for 'focus' stopped");
             <BcastStreamFocus as BcastStreamBase>::set_started_t(&source, false);
@@ -144,13 +274,15 @@ impl BcastStreamFocusWorker {
 pub struct BcastStreamNearestWorker {
     pub stream: BcastStreamNearest,
     pub url: String,
+    pub tap: Tap,
 }
 
 impl BcastStreamNearestWorker {
-    pub fn new() -> BcastStreamNearestWorker {
+    pub fn new(tap: Tap) -> BcastStreamNearestWorker {
         BcastStreamNearestWorker {
-            stream: BcastStreamNearest::new(),
+            stream: BcastStreamNearest::new(&tap.events),
             url: String::from("file:///home/stefan/devel/tpvbc2http/http/testing/nearest.json"),
+            tap,
         }
     }
 
@@ -178,70 +310,58 @@ impl BcastStreamNearestWorker {
         let source = Arc::clone(&self.stream.state);
         let nearest = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
 
         thread::spawn(move || {
             <BcastStreamNearest as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'nearest' started");
 
-            let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
-            let mut watcher = notify::recommended_watcher(tx).unwrap();
-    
-            log::info!("watching file: {}", url);
+            let mut watch = FileWatch::new(&url, &source);
 
-            // fixme: error handling
-            notify::Watcher::watch(&mut watcher, Path::new(&url), notify::RecursiveMode::NonRecursive).unwrap();
-            
             loop {
                 if !<BcastStreamNearest as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let changed: bool;
-
-                match rx.recv_timeout(time::Duration::from_millis(1000)) {
-                    Ok(_) => changed = true,
-                    Err(_) => changed = false,
-                }
-        
-                if changed {
-                    // flush all the follow up events ...
-                    loop {
-                        match rx.recv_timeout(time::Duration::from_millis(100)) {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    log::info!("'nearest' changed");
+                if watch.changed() {
+                    log::debug!("'nearest' changed");
 
-                    match fs::read_to_string(&url) {
-                        Ok(content) => (|c: &str| {
-                            let mut nearest_list: Vec<Nearest> = Vec::new();
-
-                            match serde_json::from_str(c) {
-                                Ok(obj) => nearest_list = obj,
-                                Err(err) => (|e| {
-                                    log::warn!("Faild to deserialize 'nearest' data: {}", e);
-                                })(err),
+                    match read(&url) {
+                        Ok((content, source_time)) => (|c: &str| {
+                            // rewritten with the same content
+                            if <BcastStreamNearest as BcastStreamBase>::unchanged_t(&source, c) {
+                                return;
                             }
-                            
-                            log::debug!("'nearest' json:\n{nearest_list:#?}");
-
-                            // all good, we got some data
-                            <BcastStreamNearest as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                            {
-                                let mut nearest_locked = nearest.lock().unwrap();
-                                *nearest_locked = nearest_list;
+
+                            let received = Received::now(source_time);
+                            tap.frame("nearest", c, received);
+
+                            match schema::parse::<Nearest>(c) {
+                                Ok((nearest_list, schema)) => {
+                                    log::debug!("'nearest' json:\n{nearest_list:#?}");
+
+                                    // all good, we got some data
+                                    <BcastStreamNearest as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                    <BcastStreamNearest as BcastStreamBase>::set_received_t(&source, received);
+                                    <BcastStreamNearest as BcastStreamBase>::set_schema_t(&source, schema);
+                                    {
+                                        let mut nearest_locked = nearest.lock().unwrap();
+                                        *nearest_locked = nearest_list;
+                                    }
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to deserialize 'nearest' data: {}", err);
+                                    <BcastStreamNearest as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), c);
+                                }
                             }
-                        })(&content[3..]), // remove utf-8 BOM
-                        Err(err) => (|e| {
-                            log::warn!("Failed to read 'nearest': {}", e);
-                            <BcastStreamNearest as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                        })(err),
+                        })(&content),
+                        Err(err) => {
+                            log::warn!("Failed to read 'nearest': {}", err);
+                            <BcastStreamNearest as BcastStreamBase>::set_error_t(&source, err);
+                        }
                     }
                 }
-                thread::sleep(time::Duration::from_millis(250));
             }
             log::info!("Worker thread for 'nearest' stopped");
             <BcastStreamNearest as BcastStreamBase>::set_started_t(&source, false);
@@ -253,13 +373,15 @@ impl BcastStreamNearestWorker {
 pub struct BcastStreamEventWorker {
     pub stream: BcastStreamEvent,
     pub url: String,
+    pub tap: Tap,
 }
 
 impl BcastStreamEventWorker {
-    pub fn new() -> BcastStreamEventWorker {
+    pub fn new(tap: Tap) -> BcastStreamEventWorker {
         BcastStreamEventWorker {
-            stream: BcastStreamEvent::new(),
+            stream: BcastStreamEvent::new(&tap.events),
             url: String::from("file:///home/stefan/devel/tpvbc2http/http/testing/event.json"),
+            tap,
         }
     }
 
@@ -287,78 +409,62 @@ impl BcastStreamEventWorker {
         let source = Arc::clone(&self.stream.state);
         let event = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
 
         thread::spawn(move || {
             <BcastStreamEvent as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'event' started");
 
-            let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
-            let mut watcher = notify::recommended_watcher(tx).unwrap();
-    
-            log::info!("watching file: {}", url);
+            let mut watch = FileWatch::new(&url, &source);
 
-            // fixme: error handling
-            notify::Watcher::watch(&mut watcher, Path::new(&url), notify::RecursiveMode::NonRecursive).unwrap();
-            
             loop {
                 if !<BcastStreamEvent as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let changed: bool;
+                if watch.changed() {
+                    log::debug!("'event' changed");
 
-                match rx.recv_timeout(time::Duration::from_millis(1000)) {
-                    Ok(_) => changed = true,
-                    Err(_) => changed = false,
-                }
-        
-                if changed {
-                    // flush all the follow up events ...
-                    loop {
-                        match rx.recv_timeout(time::Duration::from_millis(100)) {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    log::info!("'event' changed");
-
-                    match fs::read_to_string(&url) {
-                        Ok(content) => (|c: &str| {
-                            // 'type' is a reserved RUST keyword. Thus we can not have a field named 'type'
-                            // in the struct for the 'serde' bindings. So what we do here is to change
-                            // 'type' to 'type_' in the received body:
-                            let c_tmp = c.to_string().replace("\"type\":", "\"type_\":");
-                            let mut event_list: Vec<Event> = Vec::new();
-
-                            match serde_json::from_str(&c_tmp) {
-                                Ok(obj) => event_list = obj,
-                                Err(err) => (|e| {
-                                    log::warn!("Faild to deserialize 'event' data: {}", e);
-                                })(err),
+                    match read(&url) {
+                        Ok((content, source_time)) => (|c: &str| {
+                            // rewritten with the same content
+                            if <BcastStreamEvent as BcastStreamBase>::unchanged_t(&source, c) {
+                                return;
                             }
-                            
-                            log::debug!("'event' json:\n{event_list:#?}");
-
-                            // all good, we got some data
-                            <BcastStreamEvent as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                            {
-                                let mut event_locked = event.lock().unwrap();
 
-                                match event_list.get(0) {
-                                    Some(f) => *event_locked = f.clone(),
-                                    None => (), // empty json array -> ignore
+                            let received = Received::now(source_time);
+                            tap.frame("event", c, received);
+
+                            match schema::parse::<Event>(c) {
+                                Ok((event_list, schema)) => {
+                                    log::debug!("'event' json:\n{event_list:#?}");
+
+                                    // all good, we got some data
+                                    <BcastStreamEvent as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                    <BcastStreamEvent as BcastStreamBase>::set_received_t(&source, received);
+                                    <BcastStreamEvent as BcastStreamBase>::set_schema_t(&source, schema);
+                                    {
+                                        let mut event_locked = event.lock().unwrap();
+
+                                        // empty json array -> ignore
+                                        if let Some(f) = event_list.first() {
+                                            *event_locked = f.clone();
+                                        }
+                                    }
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to deserialize 'event' data: {}", err);
+                                    <BcastStreamEvent as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), c);
                                 }
                             }
-                        })(&content[3..]), // remove utf-8 BOM
-                        Err(err) => (|e| {
-                            log::warn!("Failed to read 'event': {}", e);
-                            <BcastStreamEvent as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                        })(err),
+                        })(&content),
+                        Err(err) => {
+                            log::warn!("Failed to read 'event': {}", err);
+                            <BcastStreamEvent as BcastStreamBase>::set_error_t(&source, err);
+                        }
                     }
                 }
-                thread::sleep(time::Duration::from_millis(250));
             }
             log::info!("Worker thread for 'event' stopped");
             <BcastStreamEvent as BcastStreamBase>::set_started_t(&source, false);
@@ -370,13 +476,15 @@ impl BcastStreamEventWorker {
 pub struct BcastStreamEntriesWorker {
     pub stream: BcastStreamEntries,
     pub url: String,
+    pub tap: Tap,
 }
 
 impl BcastStreamEntriesWorker {
-    pub fn new() -> BcastStreamEntriesWorker {
+    pub fn new(tap: Tap) -> BcastStreamEntriesWorker {
         BcastStreamEntriesWorker {
-            stream: BcastStreamEntries::new(),
+            stream: BcastStreamEntries::new(&tap.events),
             url: String::from("file:///home/stefan/devel/tpvbc2http/http/testing/entries.json"),
+            tap,
         }
     }
 
@@ -404,70 +512,58 @@ impl BcastStreamEntriesWorker {
         let source = Arc::clone(&self.stream.state);
         let entries = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
 
         thread::spawn(move || {
             <BcastStreamEntries as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'entries' started");
 
-            let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
-            let mut watcher = notify::recommended_watcher(tx).unwrap();
-    
-            log::info!("watching file: {}", url);
+            let mut watch = FileWatch::new(&url, &source);
 
-            // fixme: error handling
-            notify::Watcher::watch(&mut watcher, Path::new(&url), notify::RecursiveMode::NonRecursive).unwrap();
-            
             loop {
                 if !<BcastStreamEntries as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let changed: bool;
-
-                match rx.recv_timeout(time::Duration::from_millis(1000)) {
-                    Ok(_) => changed = true,
-                    Err(_) => changed = false,
-                }
-        
-                if changed {
-                    // flush all the follow up events ...
-                    loop {
-                        match rx.recv_timeout(time::Duration::from_millis(100)) {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    log::info!("'entries' changed");
-
-                    match fs::read_to_string(&url) {
-                        Ok(content) => (|c: &str| {
-                            let mut entries_list: Vec<Entries> = Vec::new();
+                if watch.changed() {
+                    log::debug!("'entries' changed");
 
-                            match serde_json::from_str(c) {
-                                Ok(obj) => entries_list = obj,
-                                Err(err) => (|e| {
-                                    log::warn!("Faild to deserialize 'entries' data: {}", e);
-                                })(err),
+                    match read(&url) {
+                        Ok((content, source_time)) => (|c: &str| {
+                            // rewritten with the same content
+                            if <BcastStreamEntries as BcastStreamBase>::unchanged_t(&source, c) {
+                                return;
                             }
-                            
-                            log::debug!("'entries' json:\n{entries_list:#?}");
-
-                            // all good, we got some data
-                            <BcastStreamEntries as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                            {
-                                let mut entries_locked = entries.lock().unwrap();
-                                *entries_locked = entries_list;
+
+                            let received = Received::now(source_time);
+                            tap.frame("entries", c, received);
+
+                            match schema::parse::<Entries>(c) {
+                                Ok((entries_list, schema)) => {
+                                    log::debug!("'entries' json:\n{entries_list:#?}");
+
+                                    // all good, we got some data
+                                    <BcastStreamEntries as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                    <BcastStreamEntries as BcastStreamBase>::set_received_t(&source, received);
+                                    <BcastStreamEntries as BcastStreamBase>::set_schema_t(&source, schema);
+                                    {
+                                        let mut entries_locked = entries.lock().unwrap();
+                                        *entries_locked = entries_list;
+                                    }
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to deserialize 'entries' data: {}", err);
+                                    <BcastStreamEntries as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), c);
+                                }
                             }
-                        })(&content[3..]), // remove utf-8 BOM
-                        Err(err) => (|e| {
-                            log::warn!("Failed to read 'entries': {}", e);
-                            <BcastStreamEntries as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                        })(err),
+                        })(&content),
+                        Err(err) => {
+                            log::warn!("Failed to read 'entries': {}", err);
+                            <BcastStreamEntries as BcastStreamBase>::set_error_t(&source, err);
+                        }
                     }
                 }
-                thread::sleep(time::Duration::from_millis(250));
             }
             log::info!("Worker thread for 'entries' stopped");
             <BcastStreamEntries as BcastStreamBase>::set_started_t(&source, false);
@@ -479,13 +575,15 @@ impl BcastStreamEntriesWorker {
 pub struct BcastStreamGroupsWorker {
     pub stream: BcastStreamGroups,
     pub url: String,
+    pub tap: Tap,
 }
 
 impl BcastStreamGroupsWorker {
-    pub fn new() -> BcastStreamGroupsWorker {
+    pub fn new(tap: Tap) -> BcastStreamGroupsWorker {
         BcastStreamGroupsWorker {
-            stream: BcastStreamGroups::new(),
+            stream: BcastStreamGroups::new(&tap.events),
             url: String::from("file:///home/stefan/devel/tpvbc2http/http/testing/groups.json"),
+            tap,
         }
     }
 
@@ -513,72 +611,58 @@ impl BcastStreamGroupsWorker {
         let source = Arc::clone(&self.stream.state);
         let groups = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
 
         thread::spawn(move || {
             <BcastStreamGroups as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'groups' started");
 
-            let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
-            let mut watcher = notify::recommended_watcher(tx).unwrap();
-    
-            log::info!("watching file: {}", url);
+            let mut watch = FileWatch::new(&url, &source);
 
-            // fixme: error handling
-            notify::Watcher::watch(&mut watcher, Path::new(&url), notify::RecursiveMode::NonRecursive).unwrap();
-            
             loop {
                 if !<BcastStreamGroups as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let changed: bool;
-
-                match rx.recv_timeout(time::Duration::from_millis(1000)) {
-                    Ok(_) => changed = true,
-                    Err(_) => changed = false,
-                }
-        
-                if changed {
-                    // flush all the follow up events ...
-                    loop {
-                        match rx.recv_timeout(time::Duration::from_millis(100)) {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    log::info!("'groups' changed");
-
-                    match fs::read_to_string(&url) {
-                        Ok(content) => (|c: &str| {
-                            // deal with null value
-                            let c_tmp = c.to_string().replace(": null,", ": \"\",");
-                            let mut groups_list: Vec<Groups> = Vec::new();
+                if watch.changed() {
+                    log::debug!("'groups' changed");
 
-                            match serde_json::from_str(&c_tmp) {
-                                Ok(obj) => groups_list = obj,
-                                Err(err) => (|e| {
-                                    log::warn!("Faild to deserialize 'groups' data: {}", e);
-                                })(err),
+                    match read(&url) {
+                        Ok((content, source_time)) => (|c: &str| {
+                            // rewritten with the same content
+                            if <BcastStreamGroups as BcastStreamBase>::unchanged_t(&source, c) {
+                                return;
                             }
-                            
-                            log::debug!("'groups' json:\n{groups_list:#?}");
-
-                            // all good, we got some data
-                            <BcastStreamGroups as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                            {
-                                let mut groups_locked = groups.lock().unwrap();
-                                *groups_locked = groups_list;
+
+                            let received = Received::now(source_time);
+                            tap.frame("groups", c, received);
+
+                            match schema::parse::<Groups>(c) {
+                                Ok((groups_list, schema)) => {
+                                    log::debug!("'groups' json:\n{groups_list:#?}");
+
+                                    // all good, we got some data
+                                    <BcastStreamGroups as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                    <BcastStreamGroups as BcastStreamBase>::set_received_t(&source, received);
+                                    <BcastStreamGroups as BcastStreamBase>::set_schema_t(&source, schema);
+                                    {
+                                        let mut groups_locked = groups.lock().unwrap();
+                                        *groups_locked = groups_list;
+                                    }
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to deserialize 'groups' data: {}", err);
+                                    <BcastStreamGroups as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), c);
+                                }
                             }
-                        })(&content[3..]), // remove utf-8 BOM
-                        Err(err) => (|e| {
-                            log::warn!("Failed to read 'groups': {}", e);
-                            <BcastStreamGroups as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                        })(err),
+                        })(&content),
+                        Err(err) => {
+                            log::warn!("Failed to read 'groups': {}", err);
+                            <BcastStreamGroups as BcastStreamBase>::set_error_t(&source, err);
+                        }
                     }
                 }
-                thread::sleep(time::Duration::from_millis(250));
             }
             log::info!("Worker thread for 'groups' stopped");
             <BcastStreamGroups as BcastStreamBase>::set_started_t(&source, false);
@@ -590,13 +674,15 @@ impl BcastStreamGroupsWorker {
 pub struct BcastStreamResultsIndvWorker {
     pub stream: BcastStreamResultsIndv,
     pub url: String,
+    pub tap: Tap,
 }
 
 impl BcastStreamResultsIndvWorker {
-    pub fn new() -> BcastStreamResultsIndvWorker {
+    pub fn new(tap: Tap) -> BcastStreamResultsIndvWorker {
         BcastStreamResultsIndvWorker {
-            stream: BcastStreamResultsIndv::new(),
+            stream: BcastStreamResultsIndv::new(&tap.events),
             url: String::from("file:///home/stefan/devel/tpvbc2http/http/testing/resultsIndv.json"),
+            tap,
         }
     }
 
@@ -624,70 +710,59 @@ impl BcastStreamResultsIndvWorker {
         let source = Arc::clone(&self.stream.state);
         let results_indv = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
 
         thread::spawn(move || {
             <BcastStreamResultsIndv as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'results_indv' started");
 
-            let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
-            let mut watcher = notify::recommended_watcher(tx).unwrap();
-    
-            log::info!("watching file: {}", url);
+            let mut watch = FileWatch::new(&url, &source);
 
-            // fixme: error handling
-            notify::Watcher::watch(&mut watcher, Path::new(&url), notify::RecursiveMode::NonRecursive).unwrap();
-            
             loop {
                 if !<BcastStreamResultsIndv as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let changed: bool;
-
-                match rx.recv_timeout(time::Duration::from_millis(1000)) {
-                    Ok(_) => changed = true,
-                    Err(_) => changed = false,
-                }
-        
-                if changed {
-                    // flush all the follow up events ...
-                    loop {
-                        match rx.recv_timeout(time::Duration::from_millis(100)) {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    log::info!("'results_indv' changed");
-
-                    match fs::read_to_string(&url) {
-                        Ok(content) => (|c: &str| {
-                            let mut results_indv_list: Vec<ResultsIndv> = Vec::new();
+                if watch.changed() {
+                    log::debug!("'results_indv' changed");
 
-                            match serde_json::from_str(c) {
-                                Ok(obj) => results_indv_list = obj,
-                                Err(err) => (|e| {
-                                    log::warn!("Faild to deserialize 'results_indv' data: {}", e);
-                                })(err),
+                    match read(&url) {
+                        Ok((content, source_time)) => (|c: &str| {
+                            // rewritten with the same content
+                            if <BcastStreamResultsIndv as BcastStreamBase>::unchanged_t(&source, c) {
+                                return;
                             }
-                            
-                            log::debug!("'results_indv' json:\n{results_indv_list:#?}");
-
-                            // all good, we got some data
-                            <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                            {
-                                let mut results_indv_locked = results_indv.lock().unwrap();
-                                *results_indv_locked = results_indv_list;
+
+                            let received = Received::now(source_time);
+                            tap.frame("resultsIndv", c, received);
+
+                            match schema::parse::<ResultsIndv>(c) {
+                                Ok((results_indv_list, schema)) => {
+                                    log::debug!("'results_indv' json:\n{results_indv_list:#?}");
+
+                                    // all good, we got some data
+                                    <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                    <BcastStreamResultsIndv as BcastStreamBase>::set_received_t(&source, received);
+                                    <BcastStreamResultsIndv as BcastStreamBase>::set_schema_t(&source, schema);
+                                    {
+                                        let mut results_indv_locked = results_indv.lock().unwrap();
+                                        *results_indv_locked = results_indv_list;
+                                        tap.results_indv(&results_indv_locked);
+                                    }
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to deserialize 'results_indv' data: {}", err);
+                                    <BcastStreamResultsIndv as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), c);
+                                }
                             }
-                        })(&content[3..]), // remove utf-8 BOM
-                        Err(err) => (|e| {
-                            log::warn!("Failed to read 'results_indv': {}", e);
-                            <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                        })(err),
+                        })(&content),
+                        Err(err) => {
+                            log::warn!("Failed to read 'results_indv': {}", err);
+                            <BcastStreamResultsIndv as BcastStreamBase>::set_error_t(&source, err);
+                        }
                     }
                 }
-                thread::sleep(time::Duration::from_millis(250));
             }
             log::info!("Worker thread for 'results_indv' stopped");
             <BcastStreamResultsIndv as BcastStreamBase>::set_started_t(&source, false);
@@ -699,13 +774,15 @@ impl BcastStreamResultsIndvWorker {
 pub struct BcastStreamResultsTeamWorker {
     pub stream: BcastStreamResultsTeam,
     pub url: String,
+    pub tap: Tap,
 }
 
 impl BcastStreamResultsTeamWorker {
-    pub fn new() -> BcastStreamResultsTeamWorker {
+    pub fn new(tap: Tap) -> BcastStreamResultsTeamWorker {
         BcastStreamResultsTeamWorker {
-            stream: BcastStreamResultsTeam::new(),
+            stream: BcastStreamResultsTeam::new(&tap.events),
             url: String::from("file:///home/stefan/devel/tpvbc2http/http/testing/resultsTeam.json"),
+            tap,
         }
     }
 
@@ -733,70 +810,59 @@ impl BcastStreamResultsTeamWorker {
         let source = Arc::clone(&self.stream.state);
         let results_team = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
 
         thread::spawn(move || {
             <BcastStreamResultsTeam as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'results_team' started");
 
-            let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
-            let mut watcher = notify::recommended_watcher(tx).unwrap();
-    
-            log::info!("watching file: {}", url);
+            let mut watch = FileWatch::new(&url, &source);
 
-            // fixme: error handling
-            notify::Watcher::watch(&mut watcher, Path::new(&url), notify::RecursiveMode::NonRecursive).unwrap();
-            
             loop {
                 if !<BcastStreamResultsTeam as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let changed: bool;
+                if watch.changed() {
+                    log::debug!("'results_team' changed");
 
-                match rx.recv_timeout(time::Duration::from_millis(1000)) {
-                    Ok(_) => changed = true,
-                    Err(_) => changed = false,
-                }
-        
-                if changed {
-                    // flush all the follow up events ...
-                    loop {
-                        match rx.recv_timeout(time::Duration::from_millis(100)) {
-                            Ok(_) => (),
-                            Err(_) => break,
-                        }
-                    }
-
-                    log::info!("'results_team' changed");
-
-                    match fs::read_to_string(&url) {
-                        Ok(content) => (|c: &str| {
-                            let mut results_team_list: Vec<ResultsTeam> = Vec::new();
-
-                            match serde_json::from_str(c) {
-                                Ok(obj) => results_team_list = obj,
-                                Err(err) => (|e| {
-                                    log::warn!("Faild to deserialize 'results_team' data: {}", e);
-                                })(err),
+                    match read(&url) {
+                        Ok((content, source_time)) => (|c: &str| {
+                            // rewritten with the same content
+                            if <BcastStreamResultsTeam as BcastStreamBase>::unchanged_t(&source, c) {
+                                return;
                             }
-                            
-                            log::debug!("'results_team' json:\n{results_team_list:#?}");
-
-                            // all good, we got some data
-                            <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                            {
-                                let mut results_team_locked = results_team.lock().unwrap();
-                                *results_team_locked = results_team_list;
+
+                            let received = Received::now(source_time);
+                            tap.frame("resultsTeam", c, received);
+
+                            match schema::parse::<ResultsTeam>(c) {
+                                Ok((results_team_list, schema)) => {
+                                    log::debug!("'results_team' json:\n{results_team_list:#?}");
+
+                                    // all good, we got some data
+                                    <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                    <BcastStreamResultsTeam as BcastStreamBase>::set_received_t(&source, received);
+                                    <BcastStreamResultsTeam as BcastStreamBase>::set_schema_t(&source, schema);
+                                    {
+                                        let mut results_team_locked = results_team.lock().unwrap();
+                                        *results_team_locked = results_team_list;
+                                        tap.results_team(&results_team_locked);
+                                    }
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to deserialize 'results_team' data: {}", err);
+                                    <BcastStreamResultsTeam as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), c);
+                                }
                             }
-                        })(&content[3..]), // remove utf-8 BOM
-                        Err(err) => (|e| {
-                            log::warn!("Failed to read 'results_team': {}", e);
-                            <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                        })(err),
+                        })(&content),
+                        Err(err) => {
+                            log::warn!("Failed to read 'results_team': {}", err);
+                            <BcastStreamResultsTeam as BcastStreamBase>::set_error_t(&source, err);
+                        }
                     }
                 }
-                thread::sleep(time::Duration::from_millis(250));
             }
             log::info!("Worker thread for 'results_team' stopped");
             <BcastStreamResultsTeam as BcastStreamBase>::set_started_t(&source, false);
@@ -904,21 +970,33 @@ impl BcastStreamIf for BcastStream {
         self.results_team.stream.state()
     }
 
-    fn ride(&self) -> Ride {
+    fn ride(&self) -> Arc<Ride> {
         self.focus.stream.ride()
     }
+
+    fn ride_lap(&self) {
+        self.focus.stream.lap();
+    }
+
+    fn ride_set_auto_lap(&self, auto_lap: AutoLap) {
+        self.focus.stream.set_auto_lap(auto_lap);
+    }
+
+    fn ride_set_athlete(&self, athlete: Athlete) {
+        self.focus.stream.set_athlete(athlete);
+    }
 }
 
 impl BcastStream {
-    pub fn new() -> BcastStream {
+    pub fn new(tap: Tap) -> BcastStream {
         BcastStream {
-          focus: BcastStreamFocusWorker::new(),
-          nearest: BcastStreamNearestWorker::new(),
-          event: BcastStreamEventWorker::new(),
-          entries: BcastStreamEntriesWorker::new(),
-          groups: BcastStreamGroupsWorker::new(),
-          results_indv: BcastStreamResultsIndvWorker::new(),
-          results_team: BcastStreamResultsTeamWorker::new(),
+          focus: BcastStreamFocusWorker::new(tap.clone()),
+          nearest: BcastStreamNearestWorker::new(tap.clone()),
+          event: BcastStreamEventWorker::new(tap.clone()),
+          entries: BcastStreamEntriesWorker::new(tap.clone()),
+          groups: BcastStreamGroupsWorker::new(tap.clone()),
+          results_indv: BcastStreamResultsIndvWorker::new(tap.clone()),
+          results_team: BcastStreamResultsTeamWorker::new(tap.clone()),
         }
     }
 }
\ No newline at end of file
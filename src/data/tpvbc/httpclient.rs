@@ -1,7 +1,24 @@
-use std::{sync::{Arc, Mutex}, thread, time};
+use std::{error, io, sync::{Arc, Mutex}, thread, time};
+
+use rustls::{
+    client::{
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        WebPkiServerVerifier,
+    },
+    crypto::CryptoProvider,
+    pki_types::{pem::PemObject, CertificateDer, ServerName, UnixTime},
+    DigitallySignedStruct,
+    RootCertStore,
+    SignatureScheme,
+};
 
 use super::{
     interface::BcastStreamIf, 
+    config::{BcastConfig, Pacer},
+    credentials::{Auth, Credentials},
+    schema,
+    tap::Tap,
+    BcastError, 
     BcastState, 
     BcastStatus, 
     BcastStreamBase, 
@@ -17,55 +34,182 @@ use super::{
     Focus, 
     Groups, 
     Nearest, 
+    Received, 
     ResultsIndv, 
     ResultsTeam
 };
 
-fn http_get_blocking(status: &Arc<Mutex<u16>>, body: &Arc<Mutex<String>>, url: &str) -> (u16, String) {
-    let status_clone = Arc::clone(&status);
-    let body_clone = Arc::clone(&body);
-    let request = ehttp::Request::get(url);
- 
-    log::debug!("GET {}", url);
+// accepts the trusted certificates as they are, even if self-signed, and
+// everything the well known CAs vouch for
+#[derive(Debug)]
+struct TrustedVerifier {
+    trusted: Vec<CertificateDer<'static>>,
+    webpki: Arc<WebPkiServerVerifier>,
+    provider: Arc<CryptoProvider>,
+}
 
-    ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
-        let mut status_locked = status_clone.lock().unwrap();
-        let mut body_locked = body_clone.lock().unwrap();
-
-        match result {
-            Ok(r) => (move |response: &ehttp::Response| {
-                *status_locked = response.status;
-                match response.text() {
-                    Some(s) => (move |body: &str|{
-                        *body_locked = String::from(body);
-                        // log::info!("{}", *body_locked);
-                    })(s),
-                    None => (),
-                }
+impl ServerCertVerifier for TrustedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.trusted.iter().any(|c| c.as_ref() == end_entity.as_ref()) {
+            return Ok(ServerCertVerified::assertion());
+        }
+        self.webpki.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// TLS config trusting the certificates in the PEM file `trust`, which may be
+// self-signed server certificates or the CA which signed them
+fn tls_config(trust: &str) -> Result<Arc<rustls::ClientConfig>, String> {
+    let trusted = CertificateDer::pem_file_iter(trust)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to read certificates from {}: {}", trust, e))?;
+
+    if trusted.is_empty() {
+        return Err(format!("No certificate found in {}", trust));
+    }
+
+    let mut roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    // self-signed server certificates are rejected as CA, but handled by the verifier
+    roots.add_parsable_certificates(trusted.iter().cloned());
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let webpki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), Arc::clone(&provider))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let config = rustls::ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(TrustedVerifier { trusted, webpki, provider }))
+        .with_no_client_auth();
 
-            })(&r),
-            Err(_) => (move || {
-                *status_locked = 0;
-            })(),
+    Ok(Arc::new(config))
+}
+
+fn http_agent(timeout: time::Duration, credentials: &Credentials) -> ureq::Agent {
+    let mut builder = ureq::AgentBuilder::new().timeout(timeout);
+
+    if !credentials.trust.trim().is_empty() {
+        match tls_config(credentials.trust.trim()) {
+            Ok(config) => builder = builder.tls_config(config),
+            // requests fail with a certificate error then, which tells what is wrong
+            Err(e) => log::warn!("{}", e),
         }
-    });
+    }
+
+    builder.build()
+}
+
+fn io_error(err: &io::Error) -> BcastError {
+    match err.kind() {
+        io::ErrorKind::ConnectionRefused => BcastError::ConnectionRefused,
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => BcastError::Timeout,
+        _ => BcastError::Other(err.to_string()),
+    }
+}
+
+fn transport_error(err: &ureq::Transport) -> BcastError {
+    // the io error which caused the transport error tells us what really happened
+    let mut source = error::Error::source(err);
+
+    while let Some(e) = source {
+        if let Some(io_err) = e.downcast_ref::<io::Error>() {
+            return io_error(io_err);
+        }
+        source = e.source();
+    }
+
+    BcastError::Other(err.to_string())
+}
+
+// time of the body as told by the server, the modification time if it is
+// serving the bcast files, otherwise the time it answered
+fn source_time(response: &ureq::Response) -> Option<u64> {
+    let date = response.header("Last-Modified").or(response.header("Date"))?;
+
+    match httpdate::parse_http_date(date).map(|t| t.duration_since(time::UNIX_EPOCH)) {
+        Ok(Ok(d)) => Some(d.as_millis() as u64),
+        _ => None,
+    }
+}
 
-    let last_status: u16 = *status.lock().unwrap();
-    let last_body: String = body.lock().unwrap().clone();
+fn http_get_blocking(agent: &ureq::Agent, url: &str, auth: &Auth) -> Result<(String, Option<u64>), BcastError> {
+    log::debug!("GET {}", url);
+
+    let mut request = agent.get(url);
+
+    if let Some(header) = auth.header() {
+        request = request.set("Authorization", &header);
+    }
+
+    let response = match request.call() {
+        Ok(r) => r,
+        Err(ureq::Error::Status(code, _)) => return Err(BcastError::HttpStatus(code)),
+        Err(ureq::Error::Transport(t)) => return Err(transport_error(&t)),
+    };
 
-    (last_status, last_body)
+    if response.status() != 200 {
+        return Err(BcastError::HttpStatus(response.status()));
+    }
+
+    let source = source_time(&response);
+
+    match response.into_string() {
+        Ok(body) if body.trim().is_empty() => Err(BcastError::EmptyBody),
+        Ok(body) => Ok((body, source)),
+        Err(err) => Err(io_error(&err)),
+    }
 }
 
 pub struct BcastStreamFocusWorker {
     pub stream: BcastStreamFocus,
     pub url: String,
+    pub tap: Tap,
+    pub config: BcastConfig,
+    pub credentials: Credentials,
 }
 
 impl BcastStreamFocusWorker {
-    pub fn new() -> BcastStreamFocusWorker {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStreamFocusWorker {
         BcastStreamFocusWorker {
-            stream: BcastStreamFocus::new(),
+            stream: BcastStreamFocus::new(&tap.events),
             url: String::from("http://localhost:8080/bcast/focus"),
+            tap,
+            config,
+            credentials,
         }
     }
 
@@ -94,51 +238,70 @@ impl BcastStreamFocusWorker {
         let focus = Arc::clone(&self.stream.data);
         let ride = Arc::clone(&self.stream.ride);
         let url = self.url.clone();
+        let tap = self.tap.clone();
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
 
         thread::spawn(move || {
             <BcastStreamFocus as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'focus' started");
 
-            let status:Arc<Mutex<u16>>  = Arc::new(Mutex::new(0));
-            let body:Arc<Mutex<String>>  = Arc::new(Mutex::new(String::new()));
+            let agent = http_agent(time::Duration::from_millis(config.timeout), &credentials);
+            let mut pacer = Pacer::new(config.focus, &config);
 
             loop {
                 if !<BcastStreamFocus as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let (last_status, last_body) = http_get_blocking(&status, &body, url.as_str());
-
-                if last_status != 200 {
-                    log::warn!("Failed to retrive 'focus' data");
-                    // failed to get the data
-                    <BcastStreamFocus as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                    thread::sleep(time::Duration::from_millis(1000));
-                } else {
-                    let mut focus_list: Vec<Focus> = Vec::new();
+                let requested = time::Instant::now();
+                let response = http_get_blocking(&agent, url.as_str(), &credentials.auth);
+                <BcastStreamFocus as BcastStreamBase>::set_latency_t(&source, requested.elapsed());
 
-                    match serde_json::from_str(&last_body.as_str()) {
-                        Ok(obj) => focus_list = obj,
-                        Err(_) => () 
+                match response {
+                    Err(err) => {
+                        log::warn!("Failed to retrive 'focus' data: {}", err);
+                        // failed to get the data, back off
+                        <BcastStreamFocus as BcastStreamBase>::set_error_t(&source, err);
+                        <BcastStreamFocus as BcastStreamBase>::sleep_t(&source, pacer.failed());
                     }
-                    
-                    log::debug!("'focus' json:\n{focus_list:#?}");
-
-                    // all good, we got some data
-                    <BcastStreamFocus as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                    {
-                        let mut focus_locked = focus.lock().unwrap();
-
-                        match focus_list.get(0) {
-                            Some(f) => *focus_locked = f.clone(),
-                            None => (), // empty json array -> ignore
+                    Ok((last_body, _)) if <BcastStreamFocus as BcastStreamBase>::unchanged_t(&source, &last_body) => {
+                        // TPV did not write new data yet
+                        <BcastStreamFocus as BcastStreamBase>::sleep_t(&source, pacer.ok());
+                    }
+                    Ok((last_body, source_time)) => {
+                        let received = Received::now(source_time);
+                        tap.frame("focus", &last_body, received);
+
+                        match schema::parse::<Focus>(&last_body) {
+                            Ok((focus_list, schema)) => {
+                                log::debug!("'focus' json:\n{focus_list:#?}");
+
+                                // all good, we got some data
+                                <BcastStreamFocus as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                <BcastStreamFocus as BcastStreamBase>::set_received_t(&source, received);
+                                <BcastStreamFocus as BcastStreamBase>::set_schema_t(&source, schema);
+                                {
+                                    let mut focus_locked = focus.lock().unwrap();
+
+                                    // empty json array -> ignore
+                                    if let Some(f) = focus_list.first() {
+                                        *focus_locked = f.clone();
+                                    }
+
+                                    let mut ride_locked = ride.lock().unwrap();
+                                    Arc::make_mut(&mut ride_locked).update(focus_locked.clone(), received);
+                                    tap.focus(&focus_locked, &ride_locked);
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("Failed to deserialize 'focus' data: {}", err);
+                                <BcastStreamFocus as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), &last_body);
+                            }
                         }
-
-                        let mut ride_locked = ride.lock().unwrap();
-                        ride_locked.update(focus_locked.clone());
+                        <BcastStreamFocus as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    thread::sleep(time::Duration::from_millis(250));
                 }
             }
             log::info!("Worker thread for 'focus' stopped");
@@ -151,13 +314,19 @@ impl BcastStreamFocusWorker {
 pub struct BcastStreamNearestWorker {
     pub stream: BcastStreamNearest,
     pub url: String,
+    pub tap: Tap,
+    pub config: BcastConfig,
+    pub credentials: Credentials,
 }
 
 impl BcastStreamNearestWorker {
-    pub fn new() -> BcastStreamNearestWorker {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStreamNearestWorker {
         BcastStreamNearestWorker {
-            stream: BcastStreamNearest::new(),
+            stream: BcastStreamNearest::new(&tap.events),
             url: String::from("http://localhost:8080/bcast/nearest"),
+            tap,
+            config,
+            credentials,
         }
     }
 
@@ -185,44 +354,62 @@ impl BcastStreamNearestWorker {
         let source = Arc::clone(&self.stream.state);
         let nearest = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
 
         thread::spawn(move || {
             <BcastStreamNearest as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'nearest' started");
 
-            let status:Arc<Mutex<u16>>  = Arc::new(Mutex::new(0));
-            let body:Arc<Mutex<String>>  = Arc::new(Mutex::new(String::new()));
+            let agent = http_agent(time::Duration::from_millis(config.timeout), &credentials);
+            let mut pacer = Pacer::new(config.nearest, &config);
 
             loop {
                 if !<BcastStreamNearest as BcastStreamBase>::started_t(&source) {
                     break;
                 }
-                let (last_status, last_body) = http_get_blocking(&status, &body, url.as_str());
-
-                if last_status != 200 {
-                    log::warn!("Failed to retrive 'nearest' data");
-                    // failed to get the data
-                    <BcastStreamNearest as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                    thread::sleep(time::Duration::from_millis(1000));
-                } else {
-                    let mut nearest_list: Vec<Nearest> = Vec::new();
-
-                    match serde_json::from_str(&last_body.as_str()) {
-                        Ok(obj) => nearest_list = obj,
-                        Err(_) => () 
+                let requested = time::Instant::now();
+                let response = http_get_blocking(&agent, url.as_str(), &credentials.auth);
+                <BcastStreamNearest as BcastStreamBase>::set_latency_t(&source, requested.elapsed());
+
+                match response {
+                    Err(err) => {
+                        log::warn!("Failed to retrive 'nearest' data: {}", err);
+                        // failed to get the data, back off
+                        <BcastStreamNearest as BcastStreamBase>::set_error_t(&source, err);
+                        <BcastStreamNearest as BcastStreamBase>::sleep_t(&source, pacer.failed());
+                    }
+                    Ok((last_body, _)) if <BcastStreamNearest as BcastStreamBase>::unchanged_t(&source, &last_body) => {
+                        // TPV did not write new data yet
+                        <BcastStreamNearest as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    
-                    log::debug!("'nearest' json:\n{nearest_list:#?}");
-
-                    // all good, we got some data
-                    <BcastStreamNearest as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                    {
-                        let mut nearest_locked = nearest.lock().unwrap();
-                        *nearest_locked = nearest_list;
+                    Ok((last_body, source_time)) => {
+                        let received = Received::now(source_time);
+                        tap.frame("nearest", &last_body, received);
+
+                        match schema::parse::<Nearest>(&last_body) {
+                            Ok((nearest_list, schema)) => {
+                                log::debug!("'nearest' json:\n{nearest_list:#?}");
+
+                                // all good, we got some data
+                                <BcastStreamNearest as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                <BcastStreamNearest as BcastStreamBase>::set_received_t(&source, received);
+                                <BcastStreamNearest as BcastStreamBase>::set_schema_t(&source, schema);
+                                {
+                                    let mut nearest_locked = nearest.lock().unwrap();
+                                    *nearest_locked = nearest_list;
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("Failed to deserialize 'nearest' data: {}", err);
+                                <BcastStreamNearest as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), &last_body);
+                            }
+                        }
+                        <BcastStreamNearest as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    thread::sleep(time::Duration::from_millis(1000));
-                }           
+                }
             }
             log::info!("Worker thread for 'nearest' stopped");
             <BcastStreamNearest as BcastStreamBase>::set_started_t(&source, false);
@@ -234,13 +421,19 @@ impl BcastStreamNearestWorker {
 pub struct BcastStreamEventWorker {
     pub stream: BcastStreamEvent,
     pub url: String,
+    pub tap: Tap,
+    pub config: BcastConfig,
+    pub credentials: Credentials,
 }
 
 impl BcastStreamEventWorker {
-    pub fn new() -> BcastStreamEventWorker {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStreamEventWorker {
         BcastStreamEventWorker {
-            stream: BcastStreamEvent::new(),
+            stream: BcastStreamEvent::new(&tap.events),
             url: String::from("http://localhost:8080/bcast/event"),
+            tap,
+            config,
+            credentials,
         }
     }
 
@@ -268,52 +461,66 @@ impl BcastStreamEventWorker {
         let source = Arc::clone(&self.stream.state);
         let event: Arc<Mutex<Event>> = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
 
         thread::spawn(move || {
             <BcastStreamEvent as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'event' started");
 
-            let status:Arc<Mutex<u16>>  = Arc::new(Mutex::new(0));
-            let body:Arc<Mutex<String>>  = Arc::new(Mutex::new(String::new()));
+            let agent = http_agent(time::Duration::from_millis(config.timeout), &credentials);
+            let mut pacer = Pacer::new(config.event, &config);
 
             loop {
                 if !<BcastStreamEvent as BcastStreamBase>::started_t(&source) {
                     break;
                 }
 
-                let (last_status, last_body) = http_get_blocking(&status, &body, url.as_str());
-
-                if last_status != 200 {
-                    log::warn!("Failed to retrive 'event' data");
-                    // failed to get the data
-                    <BcastStreamEvent as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                    thread::sleep(time::Duration::from_millis(1000));
-                } else {
-                    // 'type' is a reserved RUST keyword. Thus we can not have a field named 'type'
-                    // in the struct for the 'serde' bindings. So what we do here is to change
-                    // 'type' to 'type_' in the received body:
-                    let last_body = last_body.replace("\"type\":", "\"type_\":");
-                    let mut event_list: Vec<Event> = Vec::new();
-
-                    match serde_json::from_str(&last_body.as_str()) {
-                        Ok(obj) => event_list = obj,
-                        Err(_) => () 
-                    }
-                    
-                    log::debug!("'event' json:\n{event_list:#?}");
+                let requested = time::Instant::now();
+                let response = http_get_blocking(&agent, url.as_str(), &credentials.auth);
+                <BcastStreamEvent as BcastStreamBase>::set_latency_t(&source, requested.elapsed());
 
-                    // all good, we got some data
-                    <BcastStreamEvent as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                    {
-                        let mut event_locked = event.lock().unwrap();
-
-                        match event_list.get(0) {
-                            Some(f) => *event_locked = f.clone(),
-                            None => (), // empty json array -> ignore
+                match response {
+                    Err(err) => {
+                        log::warn!("Failed to retrive 'event' data: {}", err);
+                        // failed to get the data, back off
+                        <BcastStreamEvent as BcastStreamBase>::set_error_t(&source, err);
+                        <BcastStreamEvent as BcastStreamBase>::sleep_t(&source, pacer.failed());
+                    }
+                    Ok((last_body, _)) if <BcastStreamEvent as BcastStreamBase>::unchanged_t(&source, &last_body) => {
+                        // TPV did not write new data yet
+                        <BcastStreamEvent as BcastStreamBase>::sleep_t(&source, pacer.ok());
+                    }
+                    Ok((last_body, source_time)) => {
+                        let received = Received::now(source_time);
+                        tap.frame("event", &last_body, received);
+
+                        match schema::parse::<Event>(&last_body) {
+                            Ok((event_list, schema)) => {
+                                log::debug!("'event' json:\n{event_list:#?}");
+
+                                // all good, we got some data
+                                <BcastStreamEvent as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                <BcastStreamEvent as BcastStreamBase>::set_received_t(&source, received);
+                                <BcastStreamEvent as BcastStreamBase>::set_schema_t(&source, schema);
+                                {
+                                    let mut event_locked = event.lock().unwrap();
+
+                                    // empty json array -> ignore
+                                    if let Some(f) = event_list.first() {
+                                        *event_locked = f.clone();
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("Failed to deserialize 'event' data: {}", err);
+                                <BcastStreamEvent as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), &last_body);
+                            }
                         }
+                        <BcastStreamEvent as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    thread::sleep(time::Duration::from_millis(1000));
                 }
             }
             log::info!("Worker thread for 'event' stopped");
@@ -326,13 +533,19 @@ impl BcastStreamEventWorker {
 pub struct BcastStreamEntriesWorker {
     pub stream: BcastStreamEntries,
     pub url: String,
+    pub tap: Tap,
+    pub config: BcastConfig,
+    pub credentials: Credentials,
 }
 
 impl BcastStreamEntriesWorker {
-    pub fn new() -> BcastStreamEntriesWorker {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStreamEntriesWorker {
         BcastStreamEntriesWorker {
-            stream: BcastStreamEntries::new(),
+            stream: BcastStreamEntries::new(&tap.events),
             url: String::from("http://localhost:8080/bcast/entries"),
+            tap,
+            config,
+            credentials,
         }
     }
 
@@ -360,44 +573,62 @@ impl BcastStreamEntriesWorker {
         let source = Arc::clone(&self.stream.state);
         let entries = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
 
         thread::spawn(move || {
             <BcastStreamEntries as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'entries' started");
 
-            let status:Arc<Mutex<u16>>  = Arc::new(Mutex::new(0));
-            let body:Arc<Mutex<String>>  = Arc::new(Mutex::new(String::new()));
+            let agent = http_agent(time::Duration::from_millis(config.timeout), &credentials);
+            let mut pacer = Pacer::new(config.entries, &config);
 
             loop {
                 if !<BcastStreamEntries as BcastStreamBase>::started_t(&source) {
                     break;
                 }
-                let (last_status, last_body) = http_get_blocking(&status, &body, url.as_str());
-
-                if last_status != 200 {
-                    log::warn!("Failed to retrive 'entries' data");
-                    // failed to get the data
-                    <BcastStreamEntries as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                    thread::sleep(time::Duration::from_millis(1000));
-                } else {
-                    let mut entries_list: Vec<Entries> = Vec::new();
-
-                    match serde_json::from_str(&last_body.as_str()) {
-                        Ok(obj) => entries_list = obj,
-                        Err(_) => () 
+                let requested = time::Instant::now();
+                let response = http_get_blocking(&agent, url.as_str(), &credentials.auth);
+                <BcastStreamEntries as BcastStreamBase>::set_latency_t(&source, requested.elapsed());
+
+                match response {
+                    Err(err) => {
+                        log::warn!("Failed to retrive 'entries' data: {}", err);
+                        // failed to get the data, back off
+                        <BcastStreamEntries as BcastStreamBase>::set_error_t(&source, err);
+                        <BcastStreamEntries as BcastStreamBase>::sleep_t(&source, pacer.failed());
+                    }
+                    Ok((last_body, _)) if <BcastStreamEntries as BcastStreamBase>::unchanged_t(&source, &last_body) => {
+                        // TPV did not write new data yet
+                        <BcastStreamEntries as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    
-                    log::debug!("'entries' json:\n{entries_list:#?}");
-
-                    // all good, we got some data
-                    <BcastStreamEntries as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                    {
-                        let mut entries_locked = entries.lock().unwrap();
-                        *entries_locked = entries_list;
+                    Ok((last_body, source_time)) => {
+                        let received = Received::now(source_time);
+                        tap.frame("entries", &last_body, received);
+
+                        match schema::parse::<Entries>(&last_body) {
+                            Ok((entries_list, schema)) => {
+                                log::debug!("'entries' json:\n{entries_list:#?}");
+
+                                // all good, we got some data
+                                <BcastStreamEntries as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                <BcastStreamEntries as BcastStreamBase>::set_received_t(&source, received);
+                                <BcastStreamEntries as BcastStreamBase>::set_schema_t(&source, schema);
+                                {
+                                    let mut entries_locked = entries.lock().unwrap();
+                                    *entries_locked = entries_list;
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("Failed to deserialize 'entries' data: {}", err);
+                                <BcastStreamEntries as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), &last_body);
+                            }
+                        }
+                        <BcastStreamEntries as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    thread::sleep(time::Duration::from_millis(1000));
-                }           
+                }
             }
             log::info!("Worker thread for 'entries' stopped");
             <BcastStreamEntries as BcastStreamBase>::set_started_t(&source, false);
@@ -409,13 +640,19 @@ impl BcastStreamEntriesWorker {
 pub struct BcastStreamGroupsWorker {
     pub stream: BcastStreamGroups,
     pub url: String,
+    pub tap: Tap,
+    pub config: BcastConfig,
+    pub credentials: Credentials,
 }
 
 impl BcastStreamGroupsWorker {
-    pub fn new() -> BcastStreamGroupsWorker {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStreamGroupsWorker {
         BcastStreamGroupsWorker {
-            stream: BcastStreamGroups::new(),
+            stream: BcastStreamGroups::new(&tap.events),
             url: String::from("http://localhost:8080/bcast/groups"),
+            tap,
+            config,
+            credentials,
         }
     }
 
@@ -443,46 +680,62 @@ impl BcastStreamGroupsWorker {
         let source = Arc::clone(&self.stream.state);
         let groups = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
 
         thread::spawn(move || {
             <BcastStreamGroups as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'groups' started");
 
-            let status:Arc<Mutex<u16>>  = Arc::new(Mutex::new(0));
-            let body:Arc<Mutex<String>>  = Arc::new(Mutex::new(String::new()));
+            let agent = http_agent(time::Duration::from_millis(config.timeout), &credentials);
+            let mut pacer = Pacer::new(config.groups, &config);
 
             loop {
                 if !<BcastStreamGroups as BcastStreamBase>::started_t(&source) {
                     break;
                 }
-                let (last_status, last_body) = http_get_blocking(&status, &body, url.as_str());
-
-                if last_status != 200 {
-                    log::warn!("Failed to retrive 'groups' data");
-                    // failed to get the data
-                    <BcastStreamGroups as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                    thread::sleep(time::Duration::from_millis(1000));
-                } else {
-                    // deal with null value
-                    let last_body = last_body.replace(": null,", ": \"\",");
-                    let mut groups_list: Vec<Groups> = Vec::new();
-
-                    match serde_json::from_str(&last_body.as_str()) {
-                        Ok(obj) => groups_list = obj,
-                        Err(_) => () 
+                let requested = time::Instant::now();
+                let response = http_get_blocking(&agent, url.as_str(), &credentials.auth);
+                <BcastStreamGroups as BcastStreamBase>::set_latency_t(&source, requested.elapsed());
+
+                match response {
+                    Err(err) => {
+                        log::warn!("Failed to retrive 'groups' data: {}", err);
+                        // failed to get the data, back off
+                        <BcastStreamGroups as BcastStreamBase>::set_error_t(&source, err);
+                        <BcastStreamGroups as BcastStreamBase>::sleep_t(&source, pacer.failed());
+                    }
+                    Ok((last_body, _)) if <BcastStreamGroups as BcastStreamBase>::unchanged_t(&source, &last_body) => {
+                        // TPV did not write new data yet
+                        <BcastStreamGroups as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    
-                    log::debug!("'groups' json:\n{groups_list:#?}");
-
-                    // all good, we got some data
-                    <BcastStreamGroups as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                    {
-                        let mut groups_locked = groups.lock().unwrap();
-                        *groups_locked = groups_list;
+                    Ok((last_body, source_time)) => {
+                        let received = Received::now(source_time);
+                        tap.frame("groups", &last_body, received);
+
+                        match schema::parse::<Groups>(&last_body) {
+                            Ok((groups_list, schema)) => {
+                                log::debug!("'groups' json:\n{groups_list:#?}");
+
+                                // all good, we got some data
+                                <BcastStreamGroups as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                <BcastStreamGroups as BcastStreamBase>::set_received_t(&source, received);
+                                <BcastStreamGroups as BcastStreamBase>::set_schema_t(&source, schema);
+                                {
+                                    let mut groups_locked = groups.lock().unwrap();
+                                    *groups_locked = groups_list;
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("Failed to deserialize 'groups' data: {}", err);
+                                <BcastStreamGroups as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), &last_body);
+                            }
+                        }
+                        <BcastStreamGroups as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    thread::sleep(time::Duration::from_millis(1000));
-                }           
+                }
             }
             log::info!("Worker thread for 'groups' stopped");
             <BcastStreamGroups as BcastStreamBase>::set_started_t(&source, false);
@@ -494,13 +747,19 @@ impl BcastStreamGroupsWorker {
 pub struct BcastStreamResultsIndvWorker {
     pub stream: BcastStreamResultsIndv,
     pub url: String,
+    pub tap: Tap,
+    pub config: BcastConfig,
+    pub credentials: Credentials,
 }
 
 impl BcastStreamResultsIndvWorker {
-    pub fn new() -> BcastStreamResultsIndvWorker {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStreamResultsIndvWorker {
         BcastStreamResultsIndvWorker {
-            stream: BcastStreamResultsIndv::new(),
+            stream: BcastStreamResultsIndv::new(&tap.events),
             url: String::from("http://localhost:8080/bcast/resultsIndv"),
+            tap,
+            config,
+            credentials,
         }
     }
 
@@ -528,46 +787,63 @@ impl BcastStreamResultsIndvWorker {
         let source = Arc::clone(&self.stream.state);
         let results_indv = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
 
         thread::spawn(move || {
             <BcastStreamResultsIndv as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'results_indv' started");
 
-            let status:Arc<Mutex<u16>>  = Arc::new(Mutex::new(0));
-            let body:Arc<Mutex<String>>  = Arc::new(Mutex::new(String::new()));
+            let agent = http_agent(time::Duration::from_millis(config.timeout), &credentials);
+            let mut pacer = Pacer::new(config.results_indv, &config);
 
             loop {
                 if !<BcastStreamResultsIndv as BcastStreamBase>::started_t(&source) {
                     break;
                 }
-                let (last_status, last_body) = http_get_blocking(&status, &body, url.as_str());
-
-                if last_status != 200 {
-                    log::warn!("Failed to retrive 'results_indv' data");
-                    // failed to get the data
-                    <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                    thread::sleep(time::Duration::from_millis(1000));
-                } else {
-                    // deal with null value
-                    let last_body = last_body.replace(": null,", ": \"\",");
-                    let mut results_indv_list: Vec<ResultsIndv> = Vec::new();
-
-                    match serde_json::from_str(&last_body.as_str()) {
-                        Ok(obj) => results_indv_list = obj,
-                        Err(_) => () 
+                let requested = time::Instant::now();
+                let response = http_get_blocking(&agent, url.as_str(), &credentials.auth);
+                <BcastStreamResultsIndv as BcastStreamBase>::set_latency_t(&source, requested.elapsed());
+
+                match response {
+                    Err(err) => {
+                        log::warn!("Failed to retrive 'results_indv' data: {}", err);
+                        // failed to get the data, back off
+                        <BcastStreamResultsIndv as BcastStreamBase>::set_error_t(&source, err);
+                        <BcastStreamResultsIndv as BcastStreamBase>::sleep_t(&source, pacer.failed());
                     }
-                    
-                    log::debug!("'results_indv' json:\n{results_indv_list:#?}");
-
-                    // all good, we got some data
-                    <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                    {
-                        let mut results_indv_locked = results_indv.lock().unwrap();
-                        *results_indv_locked = results_indv_list;
+                    Ok((last_body, _)) if <BcastStreamResultsIndv as BcastStreamBase>::unchanged_t(&source, &last_body) => {
+                        // TPV did not write new data yet
+                        <BcastStreamResultsIndv as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    thread::sleep(time::Duration::from_millis(1000));
-                }           
+                    Ok((last_body, source_time)) => {
+                        let received = Received::now(source_time);
+                        tap.frame("resultsIndv", &last_body, received);
+
+                        match schema::parse::<ResultsIndv>(&last_body) {
+                            Ok((results_indv_list, schema)) => {
+                                log::debug!("'results_indv' json:\n{results_indv_list:#?}");
+
+                                // all good, we got some data
+                                <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                <BcastStreamResultsIndv as BcastStreamBase>::set_received_t(&source, received);
+                                <BcastStreamResultsIndv as BcastStreamBase>::set_schema_t(&source, schema);
+                                {
+                                    let mut results_indv_locked = results_indv.lock().unwrap();
+                                    *results_indv_locked = results_indv_list;
+                                    tap.results_indv(&results_indv_locked);
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("Failed to deserialize 'results_indv' data: {}", err);
+                                <BcastStreamResultsIndv as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), &last_body);
+                            }
+                        }
+                        <BcastStreamResultsIndv as BcastStreamBase>::sleep_t(&source, pacer.ok());
+                    }
+                }
             }
             log::info!("Worker thread for 'results_indv' stopped");
             <BcastStreamResultsIndv as BcastStreamBase>::set_started_t(&source, false);
@@ -579,13 +855,19 @@ impl BcastStreamResultsIndvWorker {
 pub struct BcastStreamResultsTeamWorker {
     pub stream: BcastStreamResultsTeam,
     pub url: String,
+    pub tap: Tap,
+    pub config: BcastConfig,
+    pub credentials: Credentials,
 }
 
 impl BcastStreamResultsTeamWorker {
-    pub fn new() -> BcastStreamResultsTeamWorker {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStreamResultsTeamWorker {
         BcastStreamResultsTeamWorker {
-            stream: BcastStreamResultsTeam::new(),
+            stream: BcastStreamResultsTeam::new(&tap.events),
             url: String::from("http://localhost:8080/bcast/resultsTeam"),
+            tap,
+            config,
+            credentials,
         }
     }
 
@@ -613,46 +895,63 @@ impl BcastStreamResultsTeamWorker {
         let source = Arc::clone(&self.stream.state);
         let results_team = Arc::clone(&self.stream.data);
         let url = self.url.clone();
+        let tap = self.tap.clone();
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
 
         thread::spawn(move || {
             <BcastStreamResultsTeam as BcastStreamBase>::set_started_t(&source, true);
 
             log::info!("Worker thread for 'results_team' started");
 
-            let status:Arc<Mutex<u16>>  = Arc::new(Mutex::new(0));
-            let body:Arc<Mutex<String>>  = Arc::new(Mutex::new(String::new()));
+            let agent = http_agent(time::Duration::from_millis(config.timeout), &credentials);
+            let mut pacer = Pacer::new(config.results_team, &config);
 
             loop {
                 if !<BcastStreamResultsTeam as BcastStreamBase>::started_t(&source) {
                     break;
                 }
-                let (last_status, last_body) = http_get_blocking(&status, &body, url.as_str());
-
-                if last_status != 200 {
-                    log::warn!("Failed to retrive 'results_team' data");
-                    // failed to get the data
-                    <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&source, BcastStatus::NotOk);
-                    thread::sleep(time::Duration::from_millis(1000));
-                } else {
-                    // deal with null value
-                    let last_body = last_body.replace(": null,", ": \"\",");
-                    let mut results_team_list: Vec<ResultsTeam> = Vec::new();
-
-                    match serde_json::from_str(&last_body.as_str()) {
-                        Ok(obj) => results_team_list = obj,
-                        Err(_) => () 
+                let requested = time::Instant::now();
+                let response = http_get_blocking(&agent, url.as_str(), &credentials.auth);
+                <BcastStreamResultsTeam as BcastStreamBase>::set_latency_t(&source, requested.elapsed());
+
+                match response {
+                    Err(err) => {
+                        log::warn!("Failed to retrive 'results_team' data: {}", err);
+                        // failed to get the data, back off
+                        <BcastStreamResultsTeam as BcastStreamBase>::set_error_t(&source, err);
+                        <BcastStreamResultsTeam as BcastStreamBase>::sleep_t(&source, pacer.failed());
                     }
-                    
-                    log::debug!("'results_team' json:\n{results_team_list:#?}");
-
-                    // all good, we got some data
-                    <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
-                    {
-                        let mut results_team_locked = results_team.lock().unwrap();
-                        *results_team_locked = results_team_list;
+                    Ok((last_body, _)) if <BcastStreamResultsTeam as BcastStreamBase>::unchanged_t(&source, &last_body) => {
+                        // TPV did not write new data yet
+                        <BcastStreamResultsTeam as BcastStreamBase>::sleep_t(&source, pacer.ok());
                     }
-                    thread::sleep(time::Duration::from_millis(1000));
-                }           
+                    Ok((last_body, source_time)) => {
+                        let received = Received::now(source_time);
+                        tap.frame("resultsTeam", &last_body, received);
+
+                        match schema::parse::<ResultsTeam>(&last_body) {
+                            Ok((results_team_list, schema)) => {
+                                log::debug!("'results_team' json:\n{results_team_list:#?}");
+
+                                // all good, we got some data
+                                <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&source, BcastStatus::Ok);
+                                <BcastStreamResultsTeam as BcastStreamBase>::set_received_t(&source, received);
+                                <BcastStreamResultsTeam as BcastStreamBase>::set_schema_t(&source, schema);
+                                {
+                                    let mut results_team_locked = results_team.lock().unwrap();
+                                    *results_team_locked = results_team_list;
+                                    tap.results_team(&results_team_locked);
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("Failed to deserialize 'results_team' data: {}", err);
+                                <BcastStreamResultsTeam as BcastStreamBase>::set_parse_error_t(&source, err.to_string(), &last_body);
+                            }
+                        }
+                        <BcastStreamResultsTeam as BcastStreamBase>::sleep_t(&source, pacer.ok());
+                    }
+                }
             }
             log::info!("Worker thread for 'results_team' stopped");
             <BcastStreamResultsTeam as BcastStreamBase>::set_started_t(&source, false);
@@ -760,21 +1059,33 @@ impl BcastStreamIf for BcastStream {
         self.results_team.stream.state()
     }
 
-    fn ride(&self) -> crate::data::ride::Ride {
+    fn ride(&self) -> Arc<crate::data::ride::Ride> {
         self.focus.stream.ride()
     }
+
+    fn ride_lap(&self) {
+        self.focus.stream.lap();
+    }
+
+    fn ride_set_auto_lap(&self, auto_lap: crate::data::ride::AutoLap) {
+        self.focus.stream.set_auto_lap(auto_lap);
+    }
+
+    fn ride_set_athlete(&self, athlete: crate::data::athlete::Athlete) {
+        self.focus.stream.set_athlete(athlete);
+    }
 }
 
 impl BcastStream {
-    pub fn new() -> BcastStream {
+    pub fn new(tap: Tap, config: BcastConfig, credentials: Credentials) -> BcastStream {
         BcastStream {
-            focus: BcastStreamFocusWorker::new(),
-            nearest: BcastStreamNearestWorker::new(),
-            event: BcastStreamEventWorker::new(),
-            entries: BcastStreamEntriesWorker::new(),
-            groups: BcastStreamGroupsWorker::new(),
-            results_indv: BcastStreamResultsIndvWorker::new(),
-            results_team: BcastStreamResultsTeamWorker::new(),
+            focus: BcastStreamFocusWorker::new(tap.clone(), config.clone(), credentials.clone()),
+            nearest: BcastStreamNearestWorker::new(tap.clone(), config.clone(), credentials.clone()),
+            event: BcastStreamEventWorker::new(tap.clone(), config.clone(), credentials.clone()),
+            entries: BcastStreamEntriesWorker::new(tap.clone(), config.clone(), credentials.clone()),
+            groups: BcastStreamGroupsWorker::new(tap.clone(), config.clone(), credentials.clone()),
+            results_indv: BcastStreamResultsIndvWorker::new(tap.clone(), config.clone(), credentials.clone()),
+            results_team: BcastStreamResultsTeamWorker::new(tap.clone(), config.clone(), credentials.clone()),
         }
     }
 }
\ No newline at end of file
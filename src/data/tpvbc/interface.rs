@@ -1,4 +1,8 @@
-use crate::data::ride::Ride;
+use std::sync::Arc;
+
+use crate::data::{athlete::Athlete, ride::{AutoLap, Ride}};
+
+use super::replay::ReplayState;
 
 use super::{
     BcastState,
@@ -11,6 +15,8 @@ use super::{
     ResultsTeam,
 };
 
+/// A backend reading the bcast data of one TPV instance, e.g. via HTTP or
+/// from the bcast directory. Each `Source` of the `Facade` is backed by one.
 pub trait BcastStreamIf {
     fn start(&mut self, url: String);
 
@@ -46,5 +52,24 @@ pub trait BcastStreamIf {
 
     fn results_team_state(&self) -> BcastState;
 
-    fn ride(&self) -> Ride;
+    fn ride(&self) -> Arc<Ride>;
+
+    /// Start a new split of the ride.
+    fn ride_lap(&self);
+
+    fn ride_set_auto_lap(&self, auto_lap: AutoLap);
+
+    fn ride_set_athlete(&self, athlete: Athlete);
+
+    // Playback control, only implemented by sources which replay a recorded session
+
+    fn replay_state(&self) -> Option<ReplayState> {
+        None
+    }
+
+    fn replay_set_paused(&self, _paused: bool) {}
+
+    fn replay_set_speed(&self, _speed: f32) {}
+
+    fn replay_seek(&self, _position: u64) {}
 }
@@ -0,0 +1,227 @@
+use std::{
+    io::Write,
+    sync::{atomic::{AtomicBool, Ordering}, mpsc, Arc, Mutex},
+    thread,
+    time,
+};
+
+use crate::data::ride::Ride;
+
+use super::{config::PushConfig, STREAMS};
+
+/// Name of the event carrying the aggregated ride metrics.
+pub const RIDE: &str = "ride";
+
+// events a slow client may lag behind before further events are dropped for it
+const QUEUE_SIZE: usize = 64;
+
+struct Subscriber {
+    // empty to get everything
+    streams: Vec<String>,
+    tx: mpsc::SyncSender<Arc<String>>,
+}
+
+impl Subscriber {
+    fn wants(&self, stream: &str) -> bool {
+        self.streams.is_empty() || self.streams.iter().any(|s| s == stream)
+    }
+}
+
+struct Listener {
+    addr: String,
+    running: Arc<AtomicBool>,
+}
+
+// one server-sent event, multi line data is split into several data lines
+fn sse(event: &str, data: &str) -> String {
+    let mut s = format!("event: {}\n", event);
+
+    for line in data.lines() {
+        s.push_str("data: ");
+        s.push_str(line);
+        s.push('\n');
+    }
+    s.push('\n');
+
+    s
+}
+
+/// Pushes every new frame and the updated ride as server-sent events to
+/// clients connected to `/events`. A client selects the streams it wants
+/// with e.g. `/events?streams=focus,ride`, without it gets everything.
+/// Cloning a `Push` gives another handle to the same server.
+#[derive(Clone)]
+pub struct Push {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    listener: Arc<Mutex<Option<Listener>>>,
+}
+
+impl Push {
+    pub fn new() -> Push {
+        Push {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            listener: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn start(&self, config: &PushConfig) -> Result<String, String> {
+        let mut listener_locked = self.listener.lock().unwrap();
+
+        if let Some(listener) = listener_locked.as_ref() {
+            log::warn!("Push is already running! Stop first!");
+            return Ok(listener.addr.clone());
+        }
+
+        let addr = format!("{}:{}", config.bind.trim(), config.port);
+        let http = tiny_http::Server::http(addr.as_str()).map_err(|e| format!("Failed to push on {}: {}", addr, e))?;
+        let running = Arc::new(AtomicBool::new(true));
+
+        log::info!("Pushing events on http://{}/events", addr);
+
+        *listener_locked = Some(Listener {
+            addr: addr.clone(),
+            running: Arc::clone(&running),
+        });
+
+        let subscribers = Arc::clone(&self.subscribers);
+
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                match http.recv_timeout(time::Duration::from_millis(100)) {
+                    Ok(Some(request)) => Push::accept(&subscribers, request),
+                    Ok(None) => (),
+                    Err(e) => log::warn!("Push failed to receive request: {}", e),
+                }
+            }
+            log::info!("Push on {} stopped", addr);
+        });
+
+        Ok(listener_locked.as_ref().unwrap().addr.clone())
+    }
+
+    pub fn stop(&self) {
+        if let Some(listener) = self.listener.lock().unwrap().take() {
+            listener.running.store(false, Ordering::Relaxed);
+        }
+        // dropping the senders ends all client connections
+        self.subscribers.lock().unwrap().clear();
+    }
+
+    pub fn serving(&self) -> Option<String> {
+        self.listener.lock().unwrap().as_ref().map(|l| l.addr.clone())
+    }
+
+    pub fn clients(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+
+    /// Push a received frame to all clients subscribed to `stream`.
+    pub fn frame(&self, stream: &str, body: &str) {
+        self.send(stream, || Some(sse(stream, body)));
+    }
+
+    /// Push the current values of the ride to all clients subscribed to `ride`.
+    pub fn ride(&self, ride: &Ride) {
+        self.send(RIDE, || {
+            // the whole ride grows with every sample, it is only exported
+            let received = ride.total.timestamps.last().copied().unwrap_or_default();
+            let json = serde_json::json!({
+                "timestamp": received.local,
+                "source_timestamp": received.source,
+                "data": ride.snapshot(),
+            });
+            Some(sse(RIDE, &json.to_string()))
+        });
+    }
+
+    // the event is only built if somebody wants it
+    fn send<F: FnOnce() -> Option<String>>(&self, stream: &str, event: F) {
+        let mut subscribers_locked = self.subscribers.lock().unwrap();
+
+        if !subscribers_locked.iter().any(|s| s.wants(stream)) {
+            return;
+        }
+
+        let event = match event() {
+            Some(e) => Arc::new(e),
+            None => return,
+        };
+
+        subscribers_locked.retain(|s| {
+            if !s.wants(stream) {
+                return true;
+            }
+            match s.tx.try_send(Arc::clone(&event)) {
+                Ok(_) => true,
+                Err(mpsc::TrySendError::Full(_)) => {
+                    log::debug!("Push client is too slow, dropped '{}' event", stream);
+                    true
+                }
+                Err(mpsc::TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+
+    fn accept(subscribers: &Arc<Mutex<Vec<Subscriber>>>, request: tiny_http::Request) {
+        let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+
+        if *request.method() != tiny_http::Method::Get || path != "/events" {
+            let _ = request.respond(tiny_http::Response::from_string("Not found").with_status_code(404));
+            return;
+        }
+
+        let streams: Vec<String> = query
+            .split('&')
+            .filter_map(|p| p.strip_prefix("streams="))
+            .flat_map(|v| v.split(','))
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+
+        if let Some(s) = streams.iter().find(|s| *s != RIDE && !STREAMS.contains(&s.as_str())) {
+            let _ = request.respond(tiny_http::Response::from_string(format!("Unknown stream '{}'", s)).with_status_code(400));
+            return;
+        }
+
+        log::info!("Push client {:?} connected, streams: {:?}", request.remote_addr(), streams);
+
+        let (tx, rx) = mpsc::sync_channel::<Arc<String>>(QUEUE_SIZE);
+
+        subscribers.lock().unwrap().push(Subscriber { streams, tx });
+
+        // the response never ends, so it is written by hand (tiny_http would buffer it)
+        thread::spawn(move || {
+            let mut writer = request.into_writer();
+
+            let header = "HTTP/1.1 200 OK\r\n\
+                Content-Type: text/event-stream\r\n\
+                Cache-Control: no-cache\r\n\
+                Access-Control-Allow-Origin: *\r\n\
+                Connection: close\r\n\r\n";
+
+            if writer.write_all(header.as_bytes()).and_then(|_| writer.flush()).is_err() {
+                return;
+            }
+
+            loop {
+                let data = match rx.recv_timeout(time::Duration::from_secs(15)) {
+                    Ok(event) => event,
+                    // comment line, keeps proxies from closing an idle connection
+                    Err(mpsc::RecvTimeoutError::Timeout) => Arc::new(String::from(": keep-alive\n\n")),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                };
+
+                if writer.write_all(data.as_bytes()).and_then(|_| writer.flush()).is_err() {
+                    log::info!("Push client disconnected");
+                    break;
+                }
+            }
+        });
+    }
+}
+
+impl Default for Push {
+    fn default() -> Self {
+        Push::new()
+    }
+}
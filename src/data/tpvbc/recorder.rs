@@ -0,0 +1,131 @@
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Received;
+
+/// One received bcast body as it is stored in a session archive. The archive
+/// is a JSON-lines file, one `RecordedFrame` per line, in receive order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Name of the TPV stream (e.g. `focus`, `resultsIndv`)
+    pub stream: String,
+    /// Wall-clock receive time in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    /// Time of the frame as told by the source in milliseconds since the UNIX epoch, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<u64>,
+    /// The JSON body exactly as received (UTF-8 BOM removed)
+    pub body: String,
+}
+
+struct Archive {
+    path: PathBuf,
+    writer: BufWriter<fs::File>,
+}
+
+/// Writes every frame received by the stream workers to a session archive.
+/// Cloning a `Recorder` gives another handle to the same archive, so one
+/// instance is shared between the `Facade` and all workers of a backend.
+#[derive(Clone)]
+pub struct Recorder {
+    archive: Arc<Mutex<Option<Archive>>>,
+}
+
+pub fn now_ms() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_millis() as u64,
+        Err(_) => 0,
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder {
+            archive: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Start a new session archive in `dir`. The file is named after the
+    /// current time, e.g. `tpvui-session-1729245600.jsonl`.
+    pub fn start(&self, dir: &str) -> io::Result<PathBuf> {
+        let mut archive_locked = self.archive.lock().unwrap();
+
+        if let Some(archive) = archive_locked.as_ref() {
+            log::warn!("Recording is already running! Stop first!");
+            return Ok(archive.path.clone());
+        }
+
+        fs::create_dir_all(dir)?;
+
+        let path = Path::new(dir).join(format!("tpvui-session-{}.jsonl", now_ms() / 1000));
+        let file = fs::OpenOptions::new().create_new(true).write(true).open(&path)?;
+
+        log::info!("Recording session to: {}", path.display());
+
+        *archive_locked = Some(Archive {
+            path: path.clone(),
+            writer: BufWriter::new(file),
+        });
+
+        Ok(path)
+    }
+
+    pub fn stop(&self) {
+        let mut archive_locked = self.archive.lock().unwrap();
+
+        if let Some(mut archive) = archive_locked.take() {
+            log::info!("Recording stopped: {}", archive.path.display());
+            if let Err(e) = archive.writer.flush() {
+                log::warn!("Failed to flush session archive: {}", e);
+            }
+        }
+    }
+
+    pub fn recording(&self) -> bool {
+        self.archive.lock().unwrap().is_some()
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        self.archive.lock().unwrap().as_ref().map(|a| a.path.clone())
+    }
+
+    /// Append a received body to the archive. Does nothing if not recording.
+    pub fn record(&self, stream: &str, body: &str, received: Received) {
+        let mut archive_locked = self.archive.lock().unwrap();
+
+        let archive = match archive_locked.as_mut() {
+            Some(a) => a,
+            None => return,
+        };
+
+        let frame = RecordedFrame {
+            stream: String::from(stream),
+            timestamp: received.local,
+            source: received.source,
+            body: String::from(body),
+        };
+
+        let result = serde_json::to_writer(&mut archive.writer, &frame)
+            .map_err(io::Error::from)
+            .and_then(|_| archive.writer.write_all(b"\n"))
+            .and_then(|_| archive.writer.flush());
+
+        if let Err(e) = result {
+            log::warn!("Failed to record '{}' frame, recording stopped: {}", stream, e);
+            *archive_locked = None;
+        }
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder::new()
+    }
+}
@@ -0,0 +1,501 @@
+use std::{fs, io::{BufRead, BufReader}, sync::{Arc, Mutex}, thread, time};
+
+use crate::data::{athlete::Athlete, ride::{AutoLap, Ride}};
+
+use super::{
+    interface::BcastStreamIf,
+    recorder::RecordedFrame,
+    schema,
+    tap::Tap,
+    BcastError,
+    BcastState,
+    BcastStatus,
+    BcastStreamBase,
+    BcastStreamEntries,
+    BcastStreamEvent,
+    BcastStreamFocus,
+    BcastStreamGroups,
+    BcastStreamNearest,
+    BcastStreamResultsIndv,
+    BcastStreamResultsTeam,
+    Entries,
+    Event,
+    Focus,
+    Groups,
+    Nearest,
+    Received,
+    ResultsIndv,
+    ResultsTeam
+};
+
+pub const SPEED_MIN: f32 = 0.5;
+pub const SPEED_MAX: f32 = 20.0;
+
+/// Playback position and settings of a replayed session. All times are in
+/// milliseconds relative to the first recorded frame.
+#[derive(Clone, PartialEq)]
+pub struct ReplayState {
+    pub paused: bool,
+    pub speed: f32,
+    pub position: u64,
+    pub duration: u64,
+}
+
+impl ReplayState {
+    pub fn new() -> ReplayState {
+        ReplayState {
+            paused: false,
+            speed: 1.0,
+            position: 0,
+            duration: 0,
+        }
+    }
+}
+
+impl Default for ReplayState {
+    fn default() -> Self {
+        ReplayState::new()
+    }
+}
+
+struct ReplayControl {
+    state: ReplayState,
+    seek: Option<u64>,
+}
+
+/// Plays back a session archive written by the `Recorder` and feeds the
+/// recorded frames to all seven streams with their original timing.
+pub struct BcastStream {
+    focus: BcastStreamFocus,
+    nearest: BcastStreamNearest,
+    event: BcastStreamEvent,
+    entries: BcastStreamEntries,
+    groups: BcastStreamGroups,
+    results_indv: BcastStreamResultsIndv,
+    results_team: BcastStreamResultsTeam,
+    control: Arc<Mutex<ReplayControl>>,
+    tap: Tap,
+}
+
+fn load_session(path: &str) -> Result<Vec<RecordedFrame>, String> {
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut frames: Vec<RecordedFrame> = Vec::new();
+
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<RecordedFrame>(&line) {
+            Ok(frame) => frames.push(frame),
+            Err(e) => log::warn!("Skipping invalid frame in line {}: {}", n + 1, e),
+        }
+    }
+
+    if frames.is_empty() {
+        return Err(String::from("session contains no frames"));
+    }
+
+    // frames are written in receive order, but make sure anyway
+    frames.sort_by_key(|f| f.timestamp);
+
+    Ok(frames)
+}
+
+impl BcastStream {
+    pub fn new(tap: Tap) -> BcastStream {
+        BcastStream {
+            focus: BcastStreamFocus::new(&tap.events),
+            nearest: BcastStreamNearest::new(&tap.events),
+            event: BcastStreamEvent::new(&tap.events),
+            entries: BcastStreamEntries::new(&tap.events),
+            groups: BcastStreamGroups::new(&tap.events),
+            results_indv: BcastStreamResultsIndv::new(&tap.events),
+            results_team: BcastStreamResultsTeam::new(&tap.events),
+            control: Arc::new(Mutex::new(ReplayControl {
+                state: ReplayState::new(),
+                seek: None,
+            })),
+            tap,
+        }
+    }
+
+    fn set_started_t(&self, started: bool) {
+        <BcastStreamFocus as BcastStreamBase>::set_started_t(&self.focus.state, started);
+        <BcastStreamNearest as BcastStreamBase>::set_started_t(&self.nearest.state, started);
+        <BcastStreamEvent as BcastStreamBase>::set_started_t(&self.event.state, started);
+        <BcastStreamEntries as BcastStreamBase>::set_started_t(&self.entries.state, started);
+        <BcastStreamGroups as BcastStreamBase>::set_started_t(&self.groups.state, started);
+        <BcastStreamResultsIndv as BcastStreamBase>::set_started_t(&self.results_indv.state, started);
+        <BcastStreamResultsTeam as BcastStreamBase>::set_started_t(&self.results_team.state, started);
+    }
+
+    fn update_state_t(&self, new_state: BcastStatus) {
+        <BcastStreamFocus as BcastStreamBase>::update_state_t(&self.focus.state, new_state.clone());
+        <BcastStreamNearest as BcastStreamBase>::update_state_t(&self.nearest.state, new_state.clone());
+        <BcastStreamEvent as BcastStreamBase>::update_state_t(&self.event.state, new_state.clone());
+        <BcastStreamEntries as BcastStreamBase>::update_state_t(&self.entries.state, new_state.clone());
+        <BcastStreamGroups as BcastStreamBase>::update_state_t(&self.groups.state, new_state.clone());
+        <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&self.results_indv.state, new_state.clone());
+        <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&self.results_team.state, new_state);
+    }
+
+    fn set_error_t(&self, error: BcastError) {
+        <BcastStreamFocus as BcastStreamBase>::set_error_t(&self.focus.state, error.clone());
+        <BcastStreamNearest as BcastStreamBase>::set_error_t(&self.nearest.state, error.clone());
+        <BcastStreamEvent as BcastStreamBase>::set_error_t(&self.event.state, error.clone());
+        <BcastStreamEntries as BcastStreamBase>::set_error_t(&self.entries.state, error.clone());
+        <BcastStreamGroups as BcastStreamBase>::set_error_t(&self.groups.state, error.clone());
+        <BcastStreamResultsIndv as BcastStreamBase>::set_error_t(&self.results_indv.state, error.clone());
+        <BcastStreamResultsTeam as BcastStreamBase>::set_error_t(&self.results_team.state, error);
+    }
+
+    // replayed frames keep the times they were originally received at
+    fn received(frame: &RecordedFrame) -> Received {
+        Received {
+            local: frame.timestamp,
+            source: frame.source,
+        }
+    }
+
+    fn apply(&self, frame: &RecordedFrame) {
+        let body = frame.body.as_str();
+        let received = BcastStream::received(frame);
+
+        match frame.stream.as_str() {
+            "focus" => {
+                match schema::parse::<Focus>(body) {
+                    Ok((focus_list, schema)) => {
+                        <BcastStreamFocus as BcastStreamBase>::update_state_t(&self.focus.state, BcastStatus::Ok);
+                        <BcastStreamFocus as BcastStreamBase>::set_received_t(&self.focus.state, received);
+                        <BcastStreamFocus as BcastStreamBase>::set_schema_t(&self.focus.state, schema);
+
+                        let mut focus_locked = self.focus.data.lock().unwrap();
+
+                        if let Some(f) = focus_list.first() {
+                            *focus_locked = f.clone();
+                        }
+
+                        let mut ride_locked = self.focus.ride.lock().unwrap();
+                        Arc::make_mut(&mut ride_locked).update(focus_locked.clone(), received);
+                    }
+                    Err(err) => {
+                        <BcastStreamFocus as BcastStreamBase>::set_parse_error_t(&self.focus.state, err.to_string(), body);
+                    }
+                }
+            }
+            "nearest" => {
+                match schema::parse::<Nearest>(body) {
+                    Ok((nearest_list, schema)) => {
+                        <BcastStreamNearest as BcastStreamBase>::update_state_t(&self.nearest.state, BcastStatus::Ok);
+                        <BcastStreamNearest as BcastStreamBase>::set_received_t(&self.nearest.state, received);
+                        <BcastStreamNearest as BcastStreamBase>::set_schema_t(&self.nearest.state, schema);
+                        *self.nearest.data.lock().unwrap() = nearest_list;
+                    }
+                    Err(err) => {
+                        <BcastStreamNearest as BcastStreamBase>::set_parse_error_t(&self.nearest.state, err.to_string(), body);
+                    }
+                }
+            }
+            "event" => {
+                match schema::parse::<Event>(body) {
+                    Ok((event_list, schema)) => {
+                        <BcastStreamEvent as BcastStreamBase>::update_state_t(&self.event.state, BcastStatus::Ok);
+                        <BcastStreamEvent as BcastStreamBase>::set_received_t(&self.event.state, received);
+                        <BcastStreamEvent as BcastStreamBase>::set_schema_t(&self.event.state, schema);
+
+                        if let Some(e) = event_list.first() {
+                            *self.event.data.lock().unwrap() = e.clone();
+                        }
+                    }
+                    Err(err) => {
+                        <BcastStreamEvent as BcastStreamBase>::set_parse_error_t(&self.event.state, err.to_string(), body);
+                    }
+                }
+            }
+            "entries" => {
+                match schema::parse::<Entries>(body) {
+                    Ok((entries_list, schema)) => {
+                        <BcastStreamEntries as BcastStreamBase>::update_state_t(&self.entries.state, BcastStatus::Ok);
+                        <BcastStreamEntries as BcastStreamBase>::set_received_t(&self.entries.state, received);
+                        <BcastStreamEntries as BcastStreamBase>::set_schema_t(&self.entries.state, schema);
+                        *self.entries.data.lock().unwrap() = entries_list;
+                    }
+                    Err(err) => {
+                        <BcastStreamEntries as BcastStreamBase>::set_parse_error_t(&self.entries.state, err.to_string(), body);
+                    }
+                }
+            }
+            "groups" => {
+                match schema::parse::<Groups>(body) {
+                    Ok((groups_list, schema)) => {
+                        <BcastStreamGroups as BcastStreamBase>::update_state_t(&self.groups.state, BcastStatus::Ok);
+                        <BcastStreamGroups as BcastStreamBase>::set_received_t(&self.groups.state, received);
+                        <BcastStreamGroups as BcastStreamBase>::set_schema_t(&self.groups.state, schema);
+                        *self.groups.data.lock().unwrap() = groups_list;
+                    }
+                    Err(err) => {
+                        <BcastStreamGroups as BcastStreamBase>::set_parse_error_t(&self.groups.state, err.to_string(), body);
+                    }
+                }
+            }
+            "resultsIndv" => {
+                match schema::parse::<ResultsIndv>(body) {
+                    Ok((results_indv_list, schema)) => {
+                        <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&self.results_indv.state, BcastStatus::Ok);
+                        <BcastStreamResultsIndv as BcastStreamBase>::set_received_t(&self.results_indv.state, received);
+                        <BcastStreamResultsIndv as BcastStreamBase>::set_schema_t(&self.results_indv.state, schema);
+                        *self.results_indv.data.lock().unwrap() = results_indv_list;
+                    }
+                    Err(err) => {
+                        <BcastStreamResultsIndv as BcastStreamBase>::set_parse_error_t(&self.results_indv.state, err.to_string(), body);
+                    }
+                }
+            }
+            "resultsTeam" => {
+                match schema::parse::<ResultsTeam>(body) {
+                    Ok((results_team_list, schema)) => {
+                        <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&self.results_team.state, BcastStatus::Ok);
+                        <BcastStreamResultsTeam as BcastStreamBase>::set_received_t(&self.results_team.state, received);
+                        <BcastStreamResultsTeam as BcastStreamBase>::set_schema_t(&self.results_team.state, schema);
+                        *self.results_team.data.lock().unwrap() = results_team_list;
+                    }
+                    Err(err) => {
+                        <BcastStreamResultsTeam as BcastStreamBase>::set_parse_error_t(&self.results_team.state, err.to_string(), body);
+                    }
+                }
+            }
+            s => log::warn!("Unknown stream '{}' in session", s),
+        }
+    }
+
+    fn tap(&self, frame: &RecordedFrame) {
+        self.tap.frame(&frame.stream, &frame.body, BcastStream::received(frame));
+
+        match frame.stream.as_str() {
+            "focus" => self.tap.focus(&self.focus.data.lock().unwrap(), &self.focus.ride.lock().unwrap()),
+            "resultsIndv" => self.tap.results_indv(&self.results_indv.data.lock().unwrap()),
+            "resultsTeam" => self.tap.results_team(&self.results_team.data.lock().unwrap()),
+            _ => (),
+        }
+    }
+
+    fn shallow_clone(&self) -> BcastStream {
+        BcastStream {
+            focus: self.focus.clone(),
+            nearest: self.nearest.clone(),
+            event: self.event.clone(),
+            entries: self.entries.clone(),
+            groups: self.groups.clone(),
+            results_indv: self.results_indv.clone(),
+            results_team: self.results_team.clone(),
+            control: Arc::clone(&self.control),
+            tap: self.tap.clone(),
+        }
+    }
+
+    fn collect(&self, path: String) {
+        // all members are handles to shared state, so the worker thread
+        // works on the very same streams as the facade reads from
+        let worker = self.shallow_clone();
+
+        thread::spawn(move || {
+            worker.set_started_t(true);
+
+            log::info!("Worker thread for 'replay' started");
+
+            let frames = match load_session(&path) {
+                Ok(f) => f,
+                Err(e) => {
+                    log::warn!("Failed to load session '{}': {}", path, e);
+                    worker.set_error_t(BcastError::Other(format!("Failed to load session '{}': {}", path, e)));
+                    worker.set_started_t(false);
+                    return;
+                }
+            };
+
+            let t0 = frames[0].timestamp;
+
+            {
+                let mut control_locked = worker.control.lock().unwrap();
+                control_locked.state.position = 0;
+                control_locked.state.duration = frames[frames.len() - 1].timestamp - t0;
+                control_locked.seek = None;
+            }
+
+            let mut next: usize = 0;
+            let mut last_tick = time::Instant::now();
+
+            loop {
+                if !<BcastStreamFocus as BcastStreamBase>::started_t(&worker.focus.state) {
+                    break;
+                }
+
+                let elapsed = last_tick.elapsed().as_millis() as f32;
+                last_tick = time::Instant::now();
+
+                let (position, seeked) = {
+                    let mut control_locked = worker.control.lock().unwrap();
+                    let seek = control_locked.seek.take();
+                    let state = &mut control_locked.state;
+
+                    match seek {
+                        Some(p) => state.position = p.min(state.duration),
+                        None if !state.paused => {
+                            state.position = (state.position + (elapsed * state.speed) as u64).min(state.duration);
+                        }
+                        None => (),
+                    }
+
+                    if state.position >= state.duration {
+                        state.paused = true;
+                    }
+
+                    (state.position, seek.is_some())
+                };
+
+                if seeked {
+                    // rebuild everything from the start of the session, so the
+                    // ride (and its history) looks as if we had played up to here
+                    Arc::make_mut(&mut worker.focus.ride.lock().unwrap()).reset();
+                    next = 0;
+                }
+
+                while next < frames.len() && frames[next].timestamp - t0 <= position {
+                    worker.apply(&frames[next]);
+
+                    // frames passed while catching up with a seek are not passed on
+                    if !seeked {
+                        worker.tap(&frames[next]);
+                    }
+                    next += 1;
+                }
+
+                thread::sleep(time::Duration::from_millis(20));
+            }
+            log::info!("Worker thread for 'replay' stopped");
+            worker.set_started_t(false);
+            worker.update_state_t(BcastStatus::Unknown);
+        });
+    }
+}
+
+impl BcastStreamIf for BcastStream {
+    fn start(&mut self, url: String) {
+        if self.running() {
+            return;
+        }
+
+        log::info!("BcastStream::start");
+
+        let path = match url.strip_prefix("replay://") {
+            Some(p) => p.to_string(),
+            None => url,
+        };
+
+        // a fresh replay must not continue the ride of a previous one
+        Arc::make_mut(&mut self.focus.ride.lock().unwrap()).reset();
+        self.control.lock().unwrap().state = ReplayState::new();
+
+        self.collect(path);
+    }
+
+    fn stop(&self) {
+        log::info!("BcastStream::stop");
+        <BcastStreamFocus as BcastStreamBase>::set_started_t(&self.focus.state, false);
+    }
+
+    fn running(&self) -> bool {
+        self.focus.started()
+    }
+
+    fn focus_data(&self) -> Focus {
+        self.focus.data()
+    }
+
+    fn focus_state(&self) -> BcastState {
+        self.focus.state()
+    }
+
+    fn nearest_data(&self) -> Vec<Nearest> {
+        self.nearest.data()
+    }
+
+    fn nearest_state(&self) -> BcastState {
+        self.nearest.state()
+    }
+
+    fn event_data(&self) -> Event {
+        self.event.data()
+    }
+
+    fn event_state(&self) -> BcastState {
+        self.event.state()
+    }
+
+    fn entries_data(&self) -> Vec<Entries> {
+        self.entries.data()
+    }
+
+    fn entries_state(&self) -> BcastState {
+        self.entries.state()
+    }
+
+    fn groups_data(&self) -> Vec<Groups> {
+        self.groups.data()
+    }
+
+    fn groups_state(&self) -> BcastState {
+        self.groups.state()
+    }
+
+    fn results_indv_data(&self) -> Vec<ResultsIndv> {
+        self.results_indv.data()
+    }
+
+    fn results_indv_state(&self) -> BcastState {
+        self.results_indv.state()
+    }
+
+    fn results_team_data(&self) -> Vec<ResultsTeam> {
+        self.results_team.data()
+    }
+
+    fn results_team_state(&self) -> BcastState {
+        self.results_team.state()
+    }
+
+    fn ride(&self) -> Arc<Ride> {
+        self.focus.ride()
+    }
+
+    fn ride_lap(&self) {
+        self.focus.lap();
+    }
+
+    fn ride_set_auto_lap(&self, auto_lap: AutoLap) {
+        self.focus.set_auto_lap(auto_lap);
+    }
+
+    fn ride_set_athlete(&self, athlete: Athlete) {
+        self.focus.set_athlete(athlete);
+    }
+
+    fn replay_state(&self) -> Option<ReplayState> {
+        Some(self.control.lock().unwrap().state.clone())
+    }
+
+    fn replay_set_paused(&self, paused: bool) {
+        self.control.lock().unwrap().state.paused = paused;
+    }
+
+    fn replay_set_speed(&self, speed: f32) {
+        self.control.lock().unwrap().state.speed = speed.clamp(SPEED_MIN, SPEED_MAX);
+    }
+
+    fn replay_seek(&self, position: u64) {
+        self.control.lock().unwrap().seek = Some(position);
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// Version of the TPV bcast format the models in `tpvbc` are written for.
+/// Whenever TPV changes its format, the models are updated and this is bumped.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Fields received from TPV which are not (yet) part of a model.
+pub type Extra = BTreeMap<String, Value>;
+
+/// Result of comparing a received frame against the model it is parsed into.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaInfo {
+    /// `SCHEMA_VERSION` if the frame matches the model exactly, `None` otherwise
+    pub version: Option<u32>,
+    /// Fields of the model which were not received
+    pub missing: Vec<String>,
+    /// Received fields which are unknown to the model
+    pub unknown: Vec<String>,
+}
+
+impl SchemaInfo {
+    fn detect<T: Default + Serialize>(value: &Value) -> Option<SchemaInfo> {
+        // all bcast streams are arrays of objects, look at the first one
+        let received = match value.as_array().and_then(|a| a.first()).and_then(|o| o.as_object()) {
+            Some(o) => o,
+            None => return None,
+        };
+
+        let expected = match serde_json::to_value(T::default()) {
+            Ok(Value::Object(o)) => o,
+            _ => return None,
+        };
+
+        let missing: Vec<String> = expected.keys().filter(|k| !received.contains_key(*k)).cloned().collect();
+        let unknown: Vec<String> = received.keys().filter(|k| !expected.contains_key(*k)).cloned().collect();
+        let version = if missing.is_empty() && unknown.is_empty() {
+            Some(SCHEMA_VERSION)
+        } else {
+            None
+        };
+
+        Some(SchemaInfo {
+            version,
+            missing,
+            unknown,
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self.version {
+            Some(v) => format!("TPV bcast format v{}", v),
+            None => format!(
+                "Unknown TPV bcast format (differs from v{})\nmissing: {}\nunknown: {}",
+                SCHEMA_VERSION,
+                if self.missing.is_empty() { String::from("-") } else { self.missing.join(", ") },
+                if self.unknown.is_empty() { String::from("-") } else { self.unknown.join(", ") },
+            ),
+        }
+    }
+}
+
+/// Deserialize a bcast body into a list of `T` and report how well it matched
+/// the model. Schema info is `None` for empty lists.
+pub fn parse<T>(body: &str) -> Result<(Vec<T>, Option<SchemaInfo>), serde_json::Error>
+where
+    T: DeserializeOwned + Default + Serialize,
+{
+    let value: Value = serde_json::from_str(body)?;
+    let schema = SchemaInfo::detect::<T>(&value);
+    let list: Vec<T> = serde_json::from_value(value)?;
+
+    Ok((list, schema))
+}
+
+/// Conversion of any JSON value into a model field type, used to tolerate
+/// TPV changing e.g. an integer into a float or sending `null`.
+pub trait Lenient: Sized {
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl Lenient for u32 {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => match n.as_u64() {
+                Some(u) => Some(u.min(u32::MAX as u64) as u32),
+                None => n.as_f64().map(|f| f.round().clamp(0.0, u32::MAX as f64) as u32),
+            },
+            Value::String(s) => s.trim().parse::<f64>().ok().map(|f| f.round().clamp(0.0, u32::MAX as f64) as u32),
+            _ => None,
+        }
+    }
+}
+
+impl Lenient for i32 {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Some(i.clamp(i32::MIN as i64, i32::MAX as i64) as i32),
+                None => n.as_f64().map(|f| f.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32),
+            },
+            Value::String(s) => s.trim().parse::<f64>().ok().map(|f| f.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32),
+            _ => None,
+        }
+    }
+}
+
+impl Lenient for f32 {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => n.as_f64().map(|f| f as f32),
+            Value::String(s) => s.trim().parse::<f32>().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl Lenient for bool {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(b) => Some(*b),
+            Value::Number(n) => n.as_f64().map(|f| f != 0.0),
+            Value::String(s) => s.trim().parse::<bool>().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl Lenient for String {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// `deserialize_with` helper for model fields: values of an unexpected type
+/// (including `null`) are converted if possible, otherwise the type's default is used.
+pub fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Lenient + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+
+    match T::from_value(&value) {
+        Some(v) => Ok(v),
+        None => {
+            if !value.is_null() {
+                log::debug!("Unexpected value in bcast data, using default: {}", value);
+            }
+            Ok(T::default())
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+    thread,
+    time,
+};
+
+use super::{config::ServerConfig, discovery::Announcer, STREAMS};
+
+enum Allowed {
+    Addr(IpAddr),
+    Net(IpAddr, u32),
+}
+
+impl Allowed {
+    fn parse(entry: &str) -> Option<Allowed> {
+        match entry.split_once('/') {
+            Some((addr, bits)) => {
+                let addr: IpAddr = addr.trim().parse().ok()?;
+                let bits: u32 = bits.trim().parse().ok()?;
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+
+                if bits > max {
+                    return None;
+                }
+                Some(Allowed::Net(addr, bits))
+            }
+            None => entry.trim().parse().ok().map(Allowed::Addr),
+        }
+    }
+
+    fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            Allowed::Addr(a) => a == ip,
+            Allowed::Net(IpAddr::V4(net), bits) => match ip {
+                IpAddr::V4(ip) => {
+                    let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+                    u32::from(*net) & mask == u32::from(*ip) & mask
+                }
+                IpAddr::V6(_) => false,
+            },
+            Allowed::Net(IpAddr::V6(net), bits) => match ip {
+                IpAddr::V6(ip) => {
+                    let mask = u128::MAX.checked_shl(128 - bits).unwrap_or(0);
+                    u128::from(*net) & mask == u128::from(*ip) & mask
+                }
+                IpAddr::V4(_) => false,
+            },
+        }
+    }
+}
+
+fn allow_list(allow: &str) -> Result<Vec<Allowed>, String> {
+    allow
+        .split(',')
+        .filter(|e| !e.trim().is_empty())
+        .map(|e| Allowed::parse(e).ok_or(format!("Invalid entry in allow-list: '{}'", e.trim())))
+        .collect()
+}
+
+struct Listener {
+    addr: String,
+    running: Arc<AtomicBool>,
+}
+
+/// Serves the last frame of every stream on `/bcast/<stream>`, the same way
+/// tpvbc2http does, so other TPVUI instances could use this one as HTTP source.
+/// Cloning a `Server` gives another handle to the same server.
+#[derive(Clone)]
+pub struct Server {
+    frames: Arc<Mutex<HashMap<String, String>>>,
+    listener: Arc<Mutex<Option<Listener>>>,
+    announcer: Announcer,
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server {
+            frames: Arc::new(Mutex::new(HashMap::new())),
+            listener: Arc::new(Mutex::new(None)),
+            announcer: Announcer::new(),
+        }
+    }
+
+    pub fn start(&self, config: &ServerConfig) -> Result<String, String> {
+        let mut listener_locked = self.listener.lock().unwrap();
+
+        if let Some(listener) = listener_locked.as_ref() {
+            log::warn!("Server is already running! Stop first!");
+            return Ok(listener.addr.clone());
+        }
+
+        let allowed = allow_list(&config.allow)?;
+        let addr = format!("{}:{}", config.bind.trim(), config.port);
+        let http = tiny_http::Server::http(addr.as_str()).map_err(|e| format!("Failed to serve on {}: {}", addr, e))?;
+        let running = Arc::new(AtomicBool::new(true));
+
+        log::info!("Serving bcast data on http://{}/bcast/", addr);
+
+        // serving works without, so this is not fatal
+        if config.announce {
+            if let Err(e) = self.announcer.start(config.port, &config.name) {
+                log::warn!("{}", e);
+            }
+        }
+
+        *listener_locked = Some(Listener {
+            addr: addr.clone(),
+            running: Arc::clone(&running),
+        });
+
+        let frames = Arc::clone(&self.frames);
+
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                let request = match http.recv_timeout(time::Duration::from_millis(100)) {
+                    Ok(Some(r)) => r,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        log::warn!("Server failed to receive request: {}", e);
+                        continue;
+                    }
+                };
+
+                let response = Server::respond(&frames, &allowed, &request);
+
+                if let Err(e) = request.respond(response) {
+                    log::debug!("Failed to send response: {}", e);
+                }
+            }
+            log::info!("Server on {} stopped", addr);
+        });
+
+        Ok(listener_locked.as_ref().unwrap().addr.clone())
+    }
+
+    pub fn stop(&self) {
+        if let Some(listener) = self.listener.lock().unwrap().take() {
+            listener.running.store(false, Ordering::Relaxed);
+        }
+        self.announcer.stop();
+        self.frames.lock().unwrap().clear();
+    }
+
+    pub fn serving(&self) -> Option<String> {
+        self.listener.lock().unwrap().as_ref().map(|l| l.addr.clone())
+    }
+
+    /// Make a received body available to clients. Does nothing if not serving.
+    pub fn publish(&self, stream: &str, body: &str) {
+        if self.listener.lock().unwrap().is_none() {
+            return;
+        }
+        self.frames.lock().unwrap().insert(String::from(stream), String::from(body));
+    }
+
+    fn respond(
+        frames: &Arc<Mutex<HashMap<String, String>>>,
+        allowed: &[Allowed],
+        request: &tiny_http::Request,
+    ) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+        let permitted = allowed.is_empty()
+            || request.remote_addr().is_some_and(|a| allowed.iter().any(|e| e.matches(&a.ip())));
+
+        if !permitted {
+            log::info!("Rejected request from {:?}", request.remote_addr());
+            return tiny_http::Response::from_string("Forbidden").with_status_code(403);
+        }
+
+        if *request.method() != tiny_http::Method::Get {
+            return tiny_http::Response::from_string("Method not allowed").with_status_code(405);
+        }
+
+        // ignore any query string
+        let path = request.url().split('?').next().unwrap_or_default();
+
+        let stream = match path.strip_prefix("/bcast/") {
+            Some(s) if STREAMS.contains(&s) => s,
+            _ => return tiny_http::Response::from_string("Not found").with_status_code(404),
+        };
+
+        match frames.lock().unwrap().get(stream) {
+            Some(body) => {
+                let content_type = tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+                tiny_http::Response::from_string(body.as_str()).with_header(content_type)
+            }
+            // nothing read from TPV yet
+            None => tiny_http::Response::from_string("No data").with_status_code(503),
+        }
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server::new()
+    }
+}
@@ -0,0 +1,856 @@
+use std::{collections::VecDeque, f32::consts::PI, sync::Arc, thread, time};
+
+use serde::Serialize;
+
+use crate::data::{athlete::Athlete, ride::{AutoLap, Ride}};
+
+use super::{
+    interface::BcastStreamIf,
+    schema::Extra,
+    tap::Tap,
+    BcastState,
+    BcastStatus,
+    BcastStreamBase,
+    BcastStreamEntries,
+    BcastStreamEvent,
+    BcastStreamFocus,
+    BcastStreamGroups,
+    BcastStreamNearest,
+    BcastStreamResultsIndv,
+    BcastStreamResultsTeam,
+    Entries,
+    Event,
+    Focus,
+    Groups,
+    Nearest,
+    Received,
+    ResultsIndv,
+    ResultsTeam
+};
+
+// one simulation step every 250 ms, like the 'focus' poll interval of the http source
+const STEP_MS: u64 = 250;
+// all but the 'focus' stream are updated every n-th step
+const SLOW_STREAM_STEPS: u32 = 4;
+// a new race is started this many seconds after the last rider finished
+const RESTART_AFTER: f32 = 30.0;
+
+const RIDERS: usize = 24;
+const NEAREST: usize = 2;
+const GROUP_GAP: f32 = 25.0;
+
+const TEAMS: [(&str, &str); 4] = [
+    ("Team Ventoux", "VTX"),
+    ("Galibier Racing", "GAL"),
+    ("Stelvio Cycling", "STV"),
+    ("Alpe d'Huez CC", "ADH"),
+];
+
+const COUNTRIES: [&str; 6] = ["DE", "NL", "BE", "FR", "GB", "US"];
+
+/// Small xorshift64* generator, so the simulation only depends on its seed.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in `[lo, hi)`
+    fn range(&mut self, lo: f32, hi: f32) -> f32 {
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        lo + unit * (hi - lo)
+    }
+}
+
+/// A closed circuit with a rolling height profile.
+struct Course {
+    lap_length: f32,
+    laps: u32,
+}
+
+impl Course {
+    fn altitude(&self, distance: f32) -> f32 {
+        let x = 2.0 * PI * distance / self.lap_length;
+        35.0 * x.sin() + 12.0 * (3.0 * x).sin()
+    }
+
+    fn slope(&self, distance: f32) -> f32 {
+        (self.altitude(distance + 5.0) - self.altitude(distance - 5.0)) / 10.0
+    }
+
+    fn climbing_per_lap(&self) -> f32 {
+        let mut climbed = 0.0;
+        let mut d = 0.0;
+
+        while d < self.lap_length {
+            let step = self.altitude(d + 10.0) - self.altitude(d);
+            if step > 0.0 {
+                climbed += step;
+            }
+            d += 10.0;
+        }
+        climbed
+    }
+
+    fn total_distance(&self) -> f32 {
+        self.lap_length * self.laps as f32
+    }
+}
+
+struct Rider {
+    bib: u32,
+    name: String,
+    country: String,
+    team: usize,
+    ftp: f32,
+    weight: f32,
+    phase: f32,
+    attack: f32,
+    power: f32,
+    heartrate: f32,
+    cadence: f32,
+    speed: f32,
+    distance: f32,
+    climbed: f32,
+    draft: f32,
+    laps_done: u32,
+    finished: Option<f32>,
+}
+
+struct Crossing {
+    rider: usize,
+    time: f32,
+}
+
+/// Deterministic race simulation. Rider 0 is the rider in focus.
+struct Simulation {
+    rng: Rng,
+    race: u64,
+    course: Course,
+    riders: Vec<Rider>,
+    time: f32,
+    crossings: Vec<Vec<Crossing>>,
+    power_sum: f64,
+    cadence_sum: f64,
+    heartrate_sum: f64,
+    samples: u32,
+    max_power: f32,
+    max_cadence: f32,
+    max_heartrate: f32,
+    np_window: VecDeque<f32>,
+    np_sum: f64,
+    np_count: u32,
+}
+
+impl Simulation {
+    fn new(seed: u64, race: u64) -> Simulation {
+        let mut rng = Rng::new(seed.wrapping_add(race));
+        let course = Course {
+            lap_length: rng.range(6000.0, 9000.0).round(),
+            laps: 3 + (rng.next_u64() % 4) as u32,
+        };
+        let mut riders: Vec<Rider> = Vec::new();
+
+        for i in 0..RIDERS {
+            let name = if i == 0 {
+                String::from("Sim Rider")
+            } else {
+                format!("Rider {:02}", i)
+            };
+
+            let weight = if i == 0 { 61.0 } else { rng.range(58.0, 85.0) };
+
+            riders.push(Rider {
+                bib: i as u32 + 1,
+                name,
+                country: String::from(COUNTRIES[i % COUNTRIES.len()]),
+                team: i % TEAMS.len(),
+                ftp: if i == 0 { 200.0 } else { weight * rng.range(3.0, 3.6) },
+                weight,
+                phase: rng.range(0.0, 2.0 * PI),
+                attack: 0.0,
+                power: 0.0,
+                heartrate: 70.0,
+                cadence: 0.0,
+                speed: 0.0,
+                distance: rng.range(0.0, 10.0),
+                climbed: 0.0,
+                draft: 0.0,
+                laps_done: 0,
+                finished: None,
+            });
+        }
+
+        let crossings = (0..course.laps).map(|_| Vec::new()).collect();
+
+        Simulation {
+            rng,
+            race,
+            course,
+            riders,
+            time: 0.0,
+            crossings,
+            power_sum: 0.0,
+            cadence_sum: 0.0,
+            heartrate_sum: 0.0,
+            samples: 0,
+            max_power: 0.0,
+            max_cadence: 0.0,
+            max_heartrate: 0.0,
+            np_window: VecDeque::new(),
+            np_sum: 0.0,
+            np_count: 0,
+        }
+    }
+
+    fn done(&self) -> bool {
+        let mut last: f32 = 0.0;
+
+        for r in self.riders.iter() {
+            match r.finished {
+                Some(t) => last = last.max(t),
+                None => return false,
+            }
+        }
+        self.time - last > RESTART_AFTER
+    }
+
+    fn step(&mut self, dt: f32) {
+        let second_before = self.time as u32;
+        self.time += dt;
+        let new_second = self.time as u32 != second_before;
+
+        // riders in the slipstream of someone close ahead need less power
+        let positions: Vec<f32> = self.riders.iter().map(|r| r.distance).collect();
+
+        for (i, r) in self.riders.iter_mut().enumerate() {
+            r.draft = if positions.iter().enumerate().any(|(j, d)| j != i && *d - r.distance > 0.5 && *d - r.distance < 8.0) {
+                0.35
+            } else {
+                0.0
+            };
+        }
+
+        for r in self.riders.iter_mut() {
+            let slope = self.course.slope(r.distance);
+
+            if new_second {
+                if r.attack > 0.0 {
+                    r.attack -= 1.0;
+                } else if r.finished.is_none() && self.rng.range(0.0, 1.0) < 0.004 {
+                    r.attack = self.rng.range(15.0, 40.0);
+                }
+            }
+
+            let mut intensity = 0.78 + 0.08 * (self.time / 47.0 + r.phase).sin() + 2.5 * slope.clamp(0.0, 0.08);
+
+            if r.attack > 0.0 {
+                intensity += 0.45;
+            }
+            if r.finished.is_some() {
+                intensity = 0.35;
+            }
+
+            let target = r.ftp * intensity * self.rng.range(0.95, 1.05);
+            r.power += (target - r.power) * (dt / 1.5).min(1.0);
+
+            let mass = r.weight + 8.0;
+            let resistance = mass * 9.81 * (0.004 + slope) + 0.5 * 1.2 * 0.32 * (1.0 - r.draft) * r.speed * r.speed;
+            let drive = r.power / r.speed.max(2.0);
+            r.speed = (r.speed + (drive - resistance) / mass * dt).max(1.0);
+
+            let altitude_before = self.course.altitude(r.distance);
+            r.distance += r.speed * dt;
+            let altitude_after = self.course.altitude(r.distance);
+
+            if altitude_after > altitude_before {
+                r.climbed += altitude_after - altitude_before;
+            }
+
+            let hr_target = 60.0 + 120.0 * (r.power / r.ftp).min(1.4) / 1.4;
+            r.heartrate += (hr_target - r.heartrate) * (dt / 20.0);
+
+            r.cadence = (90.0 - 250.0 * slope + self.rng.range(-3.0, 3.0)).clamp(60.0, 110.0);
+        }
+
+        for (i, r) in self.riders.iter_mut().enumerate() {
+            let laps_done = ((r.distance / self.course.lap_length) as u32).min(self.course.laps);
+
+            if laps_done > r.laps_done {
+                r.laps_done = laps_done;
+                self.crossings[laps_done as usize - 1].push(Crossing { rider: i, time: self.time });
+
+                if laps_done == self.course.laps && r.finished.is_none() {
+                    r.finished = Some(self.time);
+                }
+            }
+        }
+
+        let focus = &self.riders[0];
+
+        if focus.finished.is_none() {
+            self.power_sum += (focus.power * dt) as f64;
+            self.cadence_sum += (focus.cadence * dt) as f64;
+            self.heartrate_sum += (focus.heartrate * dt) as f64;
+            self.samples += 1;
+            self.max_power = self.max_power.max(focus.power);
+            self.max_cadence = self.max_cadence.max(focus.cadence);
+            self.max_heartrate = self.max_heartrate.max(focus.heartrate);
+
+            if new_second {
+                // normalized power from a 30 s rolling average of 1 s samples
+                self.np_window.push_back(focus.power);
+                if self.np_window.len() > 30 {
+                    self.np_window.pop_front();
+                }
+                if self.np_window.len() == 30 {
+                    let avg = self.np_window.iter().sum::<f32>() as f64 / 30.0;
+                    self.np_sum += avg.powi(4);
+                    self.np_count += 1;
+                }
+            }
+        }
+    }
+
+    fn ranking(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.riders.len()).collect();
+
+        // finished riders by finish time, everybody else by distance
+        order.sort_by(|a, b| {
+            let ra = &self.riders[*a];
+            let rb = &self.riders[*b];
+
+            match (ra.finished, rb.finished) {
+                (Some(ta), Some(tb)) => ta.total_cmp(&tb),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => rb.distance.total_cmp(&ra.distance),
+            }
+        });
+        order
+    }
+
+    fn riding_time(&self) -> f32 {
+        match self.riders[0].finished {
+            Some(t) => t,
+            None => self.time,
+        }
+    }
+
+    fn focus(&self) -> Focus {
+        let r = &self.riders[0];
+        let time = self.riding_time();
+        let samples_time = (self.samples as f32 * STEP_MS as f32 / 1000.0).max(1.0) as f64;
+        let nrm_power = if self.np_count > 0 {
+            (self.np_sum / self.np_count as f64).powf(0.25) as f32
+        } else {
+            0.0
+        };
+        let intensity = nrm_power / r.ftp;
+        let tss = time * nrm_power * intensity / (r.ftp * 3600.0) * 100.0;
+        let distance_done = r.distance.min(self.course.total_distance());
+        let next_location = (r.laps_done + 1).min(self.course.laps);
+        let position = self.ranking().iter().position(|i| *i == 0).unwrap_or(0) as u32 + 1;
+
+        Focus {
+            name: r.name.clone(),
+            country: r.country.clone(),
+            team: String::from(TEAMS[r.team].0),
+            teamCode: String::from(TEAMS[r.team].1),
+            power: r.power as u32,
+            avgPower: (self.power_sum / samples_time) as u32,
+            nrmPower: nrm_power as u32,
+            maxPower: self.max_power as u32,
+            cadence: r.cadence as u32,
+            avgCadence: (self.cadence_sum / samples_time) as u32,
+            maxCadence: self.max_cadence as u32,
+            heartrate: r.heartrate as u32,
+            avgHeartrate: (self.heartrate_sum / samples_time) as u32,
+            maxHeartrate: self.max_heartrate as u32,
+            time: time as u32,
+            distance: r.distance as u32,
+            height: r.climbed as u32,
+            speed: (r.speed * 3.6 * 275.0) as u32,
+            tss: tss as u32,
+            // roughly 1 kcal burned per kJ of work at ~24% efficiency
+            calories: (self.power_sum / 1000.0) as u32,
+            draft: (r.draft * 100.0) as u32,
+            windSpeed: (8.0 * 275.0) as u32,
+            windAngle: 270,
+            slope: (self.course.slope(r.distance) * 100.0).round() as i32,
+            eventLapsTotal: self.course.laps,
+            eventLapsDone: r.laps_done as i32,
+            eventDistanceTotal: self.course.total_distance() as u32,
+            eventDistanceDone: distance_done as u32,
+            eventDistanceToNextLocation: (next_location as f32 * self.course.lap_length - distance_done).max(0.0) as u32,
+            eventNextLocation: next_location,
+            eventPosition: position,
+            extra: Extra::new(),
+        }
+    }
+
+    fn nearest(&self) -> Vec<Nearest> {
+        let ranking = self.ranking();
+        let focus = &self.riders[0];
+        let idx = ranking.iter().position(|i| *i == 0).unwrap_or(0);
+        let from = idx.saturating_sub(NEAREST);
+        let to = (idx + NEAREST + 1).min(ranking.len());
+
+        ranking[from..to].iter().enumerate().map(|(n, i)| {
+            let r = &self.riders[*i];
+
+            Nearest {
+                name: r.name.clone(),
+                country: r.country.clone(),
+                team: String::from(TEAMS[r.team].0),
+                teamCode: String::from(TEAMS[r.team].1),
+                speed: (r.speed * 3.6 * 275.0) as u32,
+                timeGap: ((focus.distance - r.distance) / focus.speed.max(1.0)).round() as i32,
+                position: (from + n) as u32 + 1,
+                distance: r.distance as u32,
+                isEliminated: false,
+                extra: Extra::new(),
+            }
+        }).collect()
+    }
+
+    fn groups(&self) -> Vec<Groups> {
+        let ranking = self.ranking();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+
+        for i in ranking {
+            match groups.last_mut() {
+                Some(g) if self.riders[g[g.len() - 1]].distance - self.riders[i].distance < GROUP_GAP => g.push(i),
+                _ => groups.push(vec![i]),
+            }
+        }
+
+        let largest = groups.iter().map(|g| g.len()).max().unwrap_or(0);
+        let head = self.riders[groups[0][0]].distance;
+        let speed = self.riders[groups[0][0]].speed.max(1.0);
+
+        groups.iter().enumerate().map(|(n, g)| {
+            let leader = &self.riders[g[0]];
+            let ahead = if n == 0 { leader.distance } else { self.riders[groups[n - 1][0]].distance };
+
+            Groups {
+                groupNum1: n as u32 + 1,
+                groupNum2: n as u32 + 1,
+                leader: leader.name.clone(),
+                size: g.len() as u32,
+                timeGap1: ((head - leader.distance) / speed).round() as i32,
+                timeGap2: ((ahead - leader.distance) / speed).round() as i32,
+                isPeloton: g.len() == largest,
+                extra: Extra::new(),
+            }
+        }).collect()
+    }
+
+    fn event(&self) -> Event {
+        Event {
+            name: format!("tpvui Simulator Race #{}", self.race + 1),
+            route: String::from("Synthetic Circuit"),
+            laps: self.course.laps,
+            distance: self.course.total_distance() as u32,
+            height: (self.course.climbing_per_lap() * self.course.laps as f32) as u32,
+            locations: self.course.laps,
+            type_: String::from("Race"),
+            extra: Extra::new(),
+        }
+    }
+
+    fn entries(&self) -> Vec<Entries> {
+        self.riders.iter().map(|r| {
+            Entries {
+                bibNum: r.bib,
+                name: r.name.clone(),
+                country: r.country.clone(),
+                team: String::from(TEAMS[r.team].0),
+                teamCode: String::from(TEAMS[r.team].1),
+                extra: Extra::new(),
+            }
+        }).collect()
+    }
+
+    fn points(position: usize) -> u32 {
+        10u32.saturating_sub(position as u32)
+    }
+
+    fn results_indv(&self) -> Vec<ResultsIndv> {
+        let mut results: Vec<ResultsIndv> = Vec::new();
+        let mut totals: Vec<u32> = vec![0; self.riders.len()];
+
+        for (location, crossings) in self.crossings.iter().enumerate() {
+            let first = match crossings.first() {
+                Some(c) => c.time,
+                None => break,
+            };
+
+            for (p, c) in crossings.iter().enumerate() {
+                let r = &self.riders[c.rider];
+                let points = Simulation::points(p);
+                totals[c.rider] += points;
+
+                results.push(ResultsIndv {
+                    location: location as u32 + 1,
+                    position: p as u32 + 1,
+                    name: r.name.clone(),
+                    country: r.country.clone(),
+                    team: String::from(TEAMS[r.team].0),
+                    teamCode: String::from(TEAMS[r.team].1),
+                    points,
+                    pointsTotal: totals[c.rider],
+                    time: c.time as u32,
+                    deltaTime: (c.time - first) as i32,
+                    isEliminated: false,
+                    extra: Extra::new(),
+                });
+            }
+        }
+        results
+    }
+
+    fn results_team(&self) -> Vec<ResultsTeam> {
+        let mut results: Vec<ResultsTeam> = Vec::new();
+        let mut totals: Vec<u32> = vec![0; TEAMS.len()];
+
+        for (location, crossings) in self.crossings.iter().enumerate() {
+            // a team is placed with the first of its riders crossing the location
+            let mut placed: Vec<(usize, f32)> = Vec::new();
+
+            for (p, c) in crossings.iter().enumerate() {
+                let team = self.riders[c.rider].team;
+                totals[team] += Simulation::points(p);
+
+                if !placed.iter().any(|(t, _)| *t == team) {
+                    placed.push((team, c.time));
+                }
+            }
+
+            let first = match placed.first() {
+                Some((_, t)) => *t,
+                None => break,
+            };
+
+            for (p, (team, t)) in placed.iter().enumerate() {
+                results.push(ResultsTeam {
+                    location: location as u32 + 1,
+                    position: p as u32 + 1,
+                    team: String::from(TEAMS[*team].0),
+                    teamCode: String::from(TEAMS[*team].1),
+                    pointsTotal: totals[*team],
+                    time: *t,
+                    deltaTime: *t - first,
+                    extra: Extra::new(),
+                });
+            }
+        }
+        results
+    }
+}
+
+/// Generates plausible data for all seven streams from a seeded simulation,
+/// so tpvui can be used without TPV. The URL is `sim://` or `sim://<seed>`.
+pub struct BcastStream {
+    focus: BcastStreamFocus,
+    nearest: BcastStreamNearest,
+    event: BcastStreamEvent,
+    entries: BcastStreamEntries,
+    groups: BcastStreamGroups,
+    results_indv: BcastStreamResultsIndv,
+    results_team: BcastStreamResultsTeam,
+    tap: Tap,
+}
+
+impl BcastStream {
+    pub fn new(tap: Tap) -> BcastStream {
+        BcastStream {
+            focus: BcastStreamFocus::new(&tap.events),
+            nearest: BcastStreamNearest::new(&tap.events),
+            event: BcastStreamEvent::new(&tap.events),
+            entries: BcastStreamEntries::new(&tap.events),
+            groups: BcastStreamGroups::new(&tap.events),
+            results_indv: BcastStreamResultsIndv::new(&tap.events),
+            results_team: BcastStreamResultsTeam::new(&tap.events),
+            tap,
+        }
+    }
+
+    fn set_started_t(&self, started: bool) {
+        <BcastStreamFocus as BcastStreamBase>::set_started_t(&self.focus.state, started);
+        <BcastStreamNearest as BcastStreamBase>::set_started_t(&self.nearest.state, started);
+        <BcastStreamEvent as BcastStreamBase>::set_started_t(&self.event.state, started);
+        <BcastStreamEntries as BcastStreamBase>::set_started_t(&self.entries.state, started);
+        <BcastStreamGroups as BcastStreamBase>::set_started_t(&self.groups.state, started);
+        <BcastStreamResultsIndv as BcastStreamBase>::set_started_t(&self.results_indv.state, started);
+        <BcastStreamResultsTeam as BcastStreamBase>::set_started_t(&self.results_team.state, started);
+    }
+
+    fn update_state_t(&self, new_state: BcastStatus) {
+        <BcastStreamFocus as BcastStreamBase>::update_state_t(&self.focus.state, new_state.clone());
+        <BcastStreamNearest as BcastStreamBase>::update_state_t(&self.nearest.state, new_state.clone());
+        <BcastStreamEvent as BcastStreamBase>::update_state_t(&self.event.state, new_state.clone());
+        <BcastStreamEntries as BcastStreamBase>::update_state_t(&self.entries.state, new_state.clone());
+        <BcastStreamGroups as BcastStreamBase>::update_state_t(&self.groups.state, new_state.clone());
+        <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&self.results_indv.state, new_state.clone());
+        <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&self.results_team.state, new_state);
+    }
+
+    fn publish_focus(&self, sim: &Simulation) {
+        let focus = sim.focus();
+        let received = Received::now(None);
+
+        <BcastStreamFocus as BcastStreamBase>::update_state_t(&self.focus.state, BcastStatus::Ok);
+        <BcastStreamFocus as BcastStreamBase>::set_received_t(&self.focus.state, received);
+        *self.focus.data.lock().unwrap() = focus.clone();
+
+        self.tap_frame("focus", &[focus.clone()], received);
+
+        let mut ride_locked = self.focus.ride.lock().unwrap();
+        Arc::make_mut(&mut ride_locked).update(focus.clone(), received);
+        self.tap.focus(&focus, &ride_locked);
+    }
+
+    // frames are only generated as JSON if somebody wants them
+    fn tap_frame<T: Serialize>(&self, stream: &str, list: &T, received: Received) {
+        if !self.tap.listening() {
+            return;
+        }
+
+        match serde_json::to_string(list) {
+            Ok(body) => self.tap.frame(stream, &body, received),
+            Err(e) => log::warn!("Failed to serialize simulated '{}' data: {}", stream, e),
+        }
+    }
+
+    fn publish_others(&self, sim: &Simulation) {
+        let nearest = sim.nearest();
+        let event = sim.event();
+        let entries = sim.entries();
+        let groups = sim.groups();
+        let results_indv = sim.results_indv();
+        let results_team = sim.results_team();
+        let received = Received::now(None);
+
+        self.tap_frame("nearest", &nearest, received);
+        self.tap_frame("event", &[event.clone()], received);
+        self.tap_frame("entries", &entries, received);
+        self.tap_frame("groups", &groups, received);
+        self.tap_frame("resultsIndv", &results_indv, received);
+        self.tap_frame("resultsTeam", &results_team, received);
+        self.tap.results_indv(&results_indv);
+        self.tap.results_team(&results_team);
+
+        *self.nearest.data.lock().unwrap() = nearest;
+        *self.event.data.lock().unwrap() = event;
+        *self.entries.data.lock().unwrap() = entries;
+        *self.groups.data.lock().unwrap() = groups;
+        *self.results_indv.data.lock().unwrap() = results_indv;
+        *self.results_team.data.lock().unwrap() = results_team;
+
+        <BcastStreamNearest as BcastStreamBase>::update_state_t(&self.nearest.state, BcastStatus::Ok);
+        <BcastStreamNearest as BcastStreamBase>::set_received_t(&self.nearest.state, received);
+        <BcastStreamEvent as BcastStreamBase>::update_state_t(&self.event.state, BcastStatus::Ok);
+        <BcastStreamEvent as BcastStreamBase>::set_received_t(&self.event.state, received);
+        <BcastStreamEntries as BcastStreamBase>::update_state_t(&self.entries.state, BcastStatus::Ok);
+        <BcastStreamEntries as BcastStreamBase>::set_received_t(&self.entries.state, received);
+        <BcastStreamGroups as BcastStreamBase>::update_state_t(&self.groups.state, BcastStatus::Ok);
+        <BcastStreamGroups as BcastStreamBase>::set_received_t(&self.groups.state, received);
+        <BcastStreamResultsIndv as BcastStreamBase>::update_state_t(&self.results_indv.state, BcastStatus::Ok);
+        <BcastStreamResultsIndv as BcastStreamBase>::set_received_t(&self.results_indv.state, received);
+        <BcastStreamResultsTeam as BcastStreamBase>::update_state_t(&self.results_team.state, BcastStatus::Ok);
+        <BcastStreamResultsTeam as BcastStreamBase>::set_received_t(&self.results_team.state, received);
+    }
+
+    fn collect(&self, seed: u64) {
+        // all members are handles to shared state, so the worker thread
+        // works on the very same streams as the facade reads from
+        let worker = BcastStream {
+            focus: self.focus.clone(),
+            nearest: self.nearest.clone(),
+            event: self.event.clone(),
+            entries: self.entries.clone(),
+            groups: self.groups.clone(),
+            results_indv: self.results_indv.clone(),
+            results_team: self.results_team.clone(),
+            tap: self.tap.clone(),
+        };
+
+        thread::spawn(move || {
+            worker.set_started_t(true);
+
+            log::info!("Worker thread for 'simulator' started (seed {})", seed);
+
+            let mut sim = Simulation::new(seed, 0);
+            let mut step: u32 = 0;
+
+            loop {
+                if !<BcastStreamFocus as BcastStreamBase>::started_t(&worker.focus.state) {
+                    break;
+                }
+
+                sim.step(STEP_MS as f32 / 1000.0);
+
+                if sim.done() {
+                    log::info!("Simulated race finished, starting the next one");
+                    sim = Simulation::new(seed, sim.race + 1);
+                }
+
+                worker.publish_focus(&sim);
+
+                if step % SLOW_STREAM_STEPS == 0 {
+                    worker.publish_others(&sim);
+                }
+                step = step.wrapping_add(1);
+
+                thread::sleep(time::Duration::from_millis(STEP_MS));
+            }
+            log::info!("Worker thread for 'simulator' stopped");
+            worker.set_started_t(false);
+            worker.update_state_t(BcastStatus::Unknown);
+        });
+    }
+}
+
+impl BcastStreamIf for BcastStream {
+    fn start(&mut self, url: String) {
+        if self.running() {
+            return;
+        }
+
+        log::info!("BcastStream::start");
+
+        let seed = url.strip_prefix("sim://").unwrap_or("").trim_matches('/').parse::<u64>().unwrap_or(1);
+
+        Arc::make_mut(&mut self.focus.ride.lock().unwrap()).reset();
+        self.collect(seed);
+    }
+
+    fn stop(&self) {
+        log::info!("BcastStream::stop");
+        <BcastStreamFocus as BcastStreamBase>::set_started_t(&self.focus.state, false);
+    }
+
+    fn running(&self) -> bool {
+        self.focus.started()
+    }
+
+    fn focus_data(&self) -> Focus {
+        self.focus.data()
+    }
+
+    fn focus_state(&self) -> BcastState {
+        self.focus.state()
+    }
+
+    fn nearest_data(&self) -> Vec<Nearest> {
+        self.nearest.data()
+    }
+
+    fn nearest_state(&self) -> BcastState {
+        self.nearest.state()
+    }
+
+    fn event_data(&self) -> Event {
+        self.event.data()
+    }
+
+    fn event_state(&self) -> BcastState {
+        self.event.state()
+    }
+
+    fn entries_data(&self) -> Vec<Entries> {
+        self.entries.data()
+    }
+
+    fn entries_state(&self) -> BcastState {
+        self.entries.state()
+    }
+
+    fn groups_data(&self) -> Vec<Groups> {
+        self.groups.data()
+    }
+
+    fn groups_state(&self) -> BcastState {
+        self.groups.state()
+    }
+
+    fn results_indv_data(&self) -> Vec<ResultsIndv> {
+        self.results_indv.data()
+    }
+
+    fn results_indv_state(&self) -> BcastState {
+        self.results_indv.state()
+    }
+
+    fn results_team_data(&self) -> Vec<ResultsTeam> {
+        self.results_team.data()
+    }
+
+    fn results_team_state(&self) -> BcastState {
+        self.results_team.state()
+    }
+
+    fn ride(&self) -> Arc<Ride> {
+        self.focus.ride()
+    }
+
+    fn ride_lap(&self) {
+        self.focus.lap();
+    }
+
+    fn ride_set_auto_lap(&self, auto_lap: AutoLap) {
+        self.focus.set_auto_lap(auto_lap);
+    }
+
+    fn ride_set_athlete(&self, athlete: Athlete) {
+        self.focus.set_athlete(athlete);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // all streams as they would be sent, after each of `steps` steps
+    fn frames(seed: u64, steps: u32) -> Vec<String> {
+        let mut sim = Simulation::new(seed, 0);
+        let mut frames = Vec::new();
+
+        for _ in 0..steps {
+            sim.step(STEP_MS as f32 / 1000.0);
+
+            frames.push(serde_json::to_string(&sim.focus()).unwrap());
+            frames.push(serde_json::to_string(&sim.nearest()).unwrap());
+            frames.push(serde_json::to_string(&sim.event()).unwrap());
+            frames.push(serde_json::to_string(&sim.entries()).unwrap());
+            frames.push(serde_json::to_string(&sim.groups()).unwrap());
+            frames.push(serde_json::to_string(&sim.results_indv()).unwrap());
+            frames.push(serde_json::to_string(&sim.results_team()).unwrap());
+        }
+        frames
+    }
+
+    #[test]
+    fn same_seed_gives_same_frames() {
+        assert_eq!(frames(42, 400), frames(42, 400));
+    }
+
+    #[test]
+    fn different_seeds_give_different_frames() {
+        assert_ne!(frames(1, 400), frames(2, 400));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::data::ride::Ride;
+
+#[cfg(feature = "serve")]
+use super::{push::Push, server::Server};
+use super::{
+    events::{Events, Notification, NotificationKind},
+    recorder::Recorder,
+    Focus,
+    Received,
+    ResultsIndv,
+    ResultsTeam,
+};
+
+/// Everything the stream workers pass new data on to. Cloning a `Tap` gives
+/// handles to the same recorder, server, push and events, so one instance is
+/// shared between the `Facade` and all workers of a backend. Serving and
+/// pushing need the `serve` feature.
+#[derive(Clone)]
+pub struct Tap {
+    pub recorder: Recorder,
+    #[cfg(feature = "serve")]
+    pub server: Server,
+    #[cfg(feature = "serve")]
+    pub push: Push,
+    pub events: Events,
+}
+
+impl Tap {
+    pub fn new() -> Tap {
+        Tap::with_events(Events::new())
+    }
+
+    /// A tap with its own recorder, server and push, but telling `events`.
+    pub fn with_events(events: Events) -> Tap {
+        Tap {
+            recorder: Recorder::new(),
+            #[cfg(feature = "serve")]
+            server: Server::new(),
+            #[cfg(feature = "serve")]
+            push: Push::new(),
+            events,
+        }
+    }
+
+    /// True if any of the taps would do something with a frame.
+    pub fn listening(&self) -> bool {
+        self.recorder.recording() || self.serving() || self.events.subscribed()
+    }
+
+    #[cfg(feature = "serve")]
+    fn serving(&self) -> bool {
+        self.server.serving().is_some() || self.push.clients() > 0
+    }
+
+    #[cfg(not(feature = "serve"))]
+    fn serving(&self) -> bool {
+        false
+    }
+
+    /// A new frame was received for `stream`.
+    pub fn frame(&self, stream: &str, body: &str, received: Received) {
+        self.recorder.record(stream, body, received);
+        #[cfg(feature = "serve")]
+        {
+            self.server.publish(stream, body);
+            self.push.frame(stream, body);
+        }
+        self.events.emit_with(NotificationKind::Frame, || Notification::Frame {
+            source: self.events.source(),
+            stream: String::from(stream),
+            body: String::from(body),
+            received,
+        });
+    }
+
+    /// The ride was updated from a new focus frame.
+    pub fn focus(&self, focus: &Focus, ride: &Ride) {
+        #[cfg(feature = "serve")]
+        self.push.ride(ride);
+        self.events.emit_with(NotificationKind::Focus, || Notification::Focus { source: self.events.source(), focus: focus.clone() });
+        self.events.ride(ride);
+    }
+
+    pub fn results_indv(&self, results: &[ResultsIndv]) {
+        self.events.emit_with(NotificationKind::ResultsIndv, || Notification::ResultsIndv { source: self.events.source(), results: results.to_vec() });
+    }
+
+    pub fn results_team(&self, results: &[ResultsTeam]) {
+        self.events.emit_with(NotificationKind::ResultsTeam, || Notification::ResultsTeam { source: self.events.source(), results: results.to_vec() });
+    }
+}
+
+impl Default for Tap {
+    fn default() -> Self {
+        Tap::new()
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    fs,
+    io::{self, Write},
+    sync::mpsc,
+    time,
+};
+
+use crate::data::{
+    athlete::AthleteConfig,
+    ride::Ride,
+    tpvbc::{BcastStatus, Notification, Received, STREAMS},
+    Facade,
+    SourceConfig,
+};
+
+/// Name of the pseudo stream carrying the aggregated ride metrics.
+const RIDE: &str = "ride";
+
+const USAGE: &str = "\
+Usage: tpvui --headless [options]
+       tpvui [options]             (built without the app)
+
+Receives TPV bcast data without a window and prints it to stdout, one line per update.
+
+Options:
+  --url <url>         Data source, http(s)://, file://, replay:// or sim:// (default: http://localhost:8080)
+  --streams <list>    Comma separated streams to print: ride, focus, nearest, event, entries,
+                      groups, resultsIndv, resultsTeam (default: ride)
+  --format <format>   json (JSON lines) or csv, csv only with --streams ride (default: json)
+  --record <dir>      Record the session to a new archive in <dir>
+  --export <file>     Write the ride (with laps and zones) as JSON to <file> when done
+  --duration <secs>   Stop after <secs> seconds, a replayed session stops at its end
+  --speed <factor>    Replay speed (default: 1)
+  --weight <kg>       Weight of the athlete (default: 61)
+  --lthr <bpm>        Heart rate threshold of the athlete (default: 171)
+  --ftp <W>           FTP of the athlete, also used as critical power (default: 200)
+  --w-prime <J>       W' of the athlete (default: 20000)
+  --retry <secs>      Give up if the source fails for longer than <secs> (default: 30)
+  --help              Show this help
+
+Exits with 1 if the source stopped (e.g. a session could not be loaded) or gave up.
+";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Csv,
+}
+
+/// Runs the `Facade` without eframe, for servers without a display.
+pub struct Headless {
+    url: String,
+    streams: Vec<String>,
+    format: Format,
+    record: Option<String>,
+    export: Option<String>,
+    duration: Option<u64>,
+    speed: f32,
+    retry: u64,
+    athlete: AthleteConfig,
+}
+
+impl Headless {
+    /// Parse the command line (without the program name). `Ok(None)` if only
+    /// the help was asked for.
+    pub fn from_args(args: &[String]) -> Result<Option<Headless>, String> {
+        let mut headless = Headless {
+            url: String::from("http://localhost:8080"),
+            streams: vec![String::from(RIDE)],
+            format: Format::Json,
+            record: None,
+            export: None,
+            duration: None,
+            speed: 1.0,
+            retry: 30,
+            athlete: AthleteConfig::new(),
+        };
+
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().cloned().ok_or(format!("Missing value for {}", arg));
+
+            match arg.as_str() {
+                "--headless" => (),
+                "--help" | "-h" => {
+                    print!("{}", USAGE);
+                    return Ok(None);
+                }
+                "--url" => headless.url = value()?,
+                "--streams" => {
+                    headless.streams = value()?.split(',').map(|s| String::from(s.trim())).filter(|s| !s.is_empty()).collect();
+
+                    if let Some(s) = headless.streams.iter().find(|s| *s != RIDE && !STREAMS.contains(&s.as_str())) {
+                        return Err(format!("Unknown stream '{}'", s));
+                    }
+                }
+                "--format" => {
+                    headless.format = match value()?.as_str() {
+                        "json" => Format::Json,
+                        "csv" => Format::Csv,
+                        f => return Err(format!("Unknown format '{}'", f)),
+                    }
+                }
+                "--record" => headless.record = Some(value()?),
+                "--export" => headless.export = Some(value()?),
+                "--duration" => headless.duration = Some(value()?.parse().map_err(|e| format!("Invalid duration: {}", e))?),
+                "--speed" => headless.speed = value()?.parse().map_err(|e| format!("Invalid speed: {}", e))?,
+                "--weight" => headless.athlete.weight = value()?.parse().map_err(|e| format!("Invalid weight: {}", e))?,
+                "--lthr" => headless.athlete.hr_threshold = value()?.parse().map_err(|e| format!("Invalid HR threshold: {}", e))?,
+                "--ftp" => headless.athlete.pwr_threshold = value()?.parse().map_err(|e| format!("Invalid FTP: {}", e))?,
+                "--w-prime" => headless.athlete.w_prime = value()?.parse().map_err(|e| format!("Invalid W': {}", e))?,
+                "--retry" => headless.retry = value()?.parse().map_err(|e| format!("Invalid retry: {}", e))?,
+                a => return Err(format!("Unknown option '{}'\n\n{}", a, USAGE)),
+            }
+        }
+
+        // other streams are JSON, they would end up between the CSV lines
+        if headless.format == Format::Csv && headless.streams.iter().any(|s| s != RIDE) {
+            return Err(String::from("CSV only applies to the ride, use --format json for other streams"));
+        }
+
+        Ok(Some(headless))
+    }
+
+    /// Receive and print until the duration passed or a replay ended. Fails
+    /// if the source stopped or failed for longer than the retry time.
+    pub fn run(&self) -> io::Result<()> {
+        let mut df = Facade::new();
+        let notifications = df.subscribe();
+
+        df.set_athlete(self.athlete);
+        df.start(&[SourceConfig::new("headless", &self.url)]);
+
+        if let Some(dir) = &self.record {
+            let path = df.start_recording(dir)?;
+            log::info!("Recording to {}", path.display());
+        }
+
+        df.sources()[0].replay_set_speed(self.speed);
+
+        let mut out = io::stdout().lock();
+
+        if self.format == Format::Csv && self.streams.iter().any(|s| s == RIDE) {
+            writeln!(out, "timestamp,source_timestamp,time,distance,speed,power,wpk,hr,cadence,ascend,slope,lap,calories")?;
+        }
+
+        let started = time::Instant::now();
+        let mut failing_since: Option<time::Instant> = None;
+        let mut failure: Option<String> = None;
+        let mut was_running = false;
+
+        loop {
+            if self.duration.is_some_and(|d| started.elapsed().as_secs() >= d) {
+                break;
+            }
+
+            let state = df.sources()[0].tpv_focus_state();
+            let error = || state.last_error_text.clone().unwrap_or(String::from("unknown error"));
+
+            // the workers set themselves running, which may take a moment. Failing
+            // meanwhile is left to the retry below, unless the worker already gave up
+            // (e.g. a session which could not be loaded).
+            if df.sources()[0].running() {
+                was_running = true;
+            } else if was_running || (state.stopped && state.status == BcastStatus::NotOk) {
+                failure = Some(if state.status == BcastStatus::NotOk {
+                    format!("Source failed: {}", error())
+                } else {
+                    String::from("Source stopped")
+                });
+                break;
+            }
+
+            // HTTP and file sources keep trying, e.g. until TPV was started
+            if state.status == BcastStatus::NotOk {
+                let since = *failing_since.get_or_insert_with(time::Instant::now);
+
+                if since.elapsed().as_secs() >= self.retry {
+                    failure = Some(format!("Source failed for {} s, giving up: {}", self.retry, error()));
+                    break;
+                }
+            } else {
+                failing_since = None;
+            }
+
+            // a replay pauses at the end of the session
+            if df.sources()[0].replay_state().is_some_and(|s| s.paused && s.position >= s.duration) {
+                break;
+            }
+
+            let notification = match notifications.recv_timeout(time::Duration::from_millis(100)) {
+                Ok(n) => n,
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+
+            match notification {
+                Notification::Focus { .. } if self.wants(RIDE) => {
+                    // the ride only has a new sample once the time advanced, the focus frame always does
+                    let received = df.sources()[0].tpv_focus_state().received.unwrap_or_default();
+                    let ride = df.sources()[0].ride();
+
+                    // nothing but zeros before the ride started
+                    if ride.total.times.len() > 1 {
+                        self.print_ride(&mut out, &ride, received)?
+                    }
+                }
+                Notification::Frame { stream, body, received, .. } if self.wants(&stream) => {
+                    // bodies are JSON, but print them as string if TPV sent garbage
+                    let data = serde_json::from_str::<serde_json::Value>(&body).unwrap_or(serde_json::Value::String(body));
+                    let line = serde_json::json!({
+                        "stream": stream,
+                        "timestamp": received.local,
+                        "source_timestamp": received.source,
+                        "data": data,
+                    });
+                    writeln!(out, "{}", line)?;
+                }
+                _ => (),
+            }
+            out.flush()?;
+        }
+
+        df.stop_recording();
+        df.stop();
+
+        if let Some(path) = &self.export {
+            let json = serde_json::to_string_pretty(&*df.sources()[0].ride()).map_err(io::Error::other)?;
+            fs::write(path, json)?;
+            log::info!("Ride exported to {}", path);
+        }
+
+        match failure {
+            Some(f) => Err(io::Error::other(f)),
+            None => Ok(()),
+        }
+    }
+
+    fn wants(&self, stream: &str) -> bool {
+        self.streams.iter().any(|s| s == stream)
+    }
+
+    fn print_ride(&self, out: &mut impl Write, ride: &Ride, received: Received) -> io::Result<()> {
+        // the current values only, the history grows with the ride
+        let s = ride.snapshot();
+
+        match self.format {
+            Format::Csv => writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                received.local, received.source.map(|t| t.to_string()).unwrap_or_default(), s.time, s.distance, s.speed, s.power, s.wpk, s.hr,
+                s.cadence, s.ascend, s.slope, s.lap, s.calories
+            ),
+            Format::Json => {
+                let line = serde_json::json!({
+                    "stream": RIDE,
+                    "timestamp": received.local,
+                    "source_timestamp": received.source,
+                    "data": s,
+                });
+                writeln!(out, "{}", line)
+            }
+        }
+    }
+}
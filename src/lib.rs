@@ -1,6 +1,48 @@
+//! Receive, record and analyze the bcast data of TrainingPeaks Virtual.
+//!
+//! Besides the `tpvui` app, the crate can be used as a library. The `data`
+//! module is its public API: `data::Facade` follows one or more TPV
+//! instances, each `data::Source` gives the parsed streams
+//! (`data::tpvbc`), their status and the `data::ride::Ride` built from them.
+//! Changes are told by `data::Notification`s, `Facade::subscribe_to` picks
+//! only some kinds of them. `data::BcastStreamIf` is the
+//! interface all backends (HTTP, file system, replay, simulator) implement.
+//!
+//! ```no_run
+//! use tpvui::data::{Facade, Notification, SourceConfig};
+//!
+//! let mut df = Facade::new();
+//! let notifications = df.subscribe();
+//!
+//! df.start(&[SourceConfig::new("TPV", "http://localhost:8080")]);
+//!
+//! for notification in notifications {
+//!     // the notifications of all sources come in on the same channel
+//!     if let Notification::Focus { source, .. } = notification {
+//!         let source = &df.sources()[source];
+//!         println!("{}: {} W", source.name(), source.ride().total.power.cur);
+//!     }
+//! }
+//! ```
+//!
+//! Cargo features, all enabled by default:
+//!
+//! * `gui`: the egui app (`TpvUiApp`), without it the `tpvui` binary only runs headless
+//! * `http-source`: read bcast data via HTTP(S), `http://` and `https://` URLs
+//! * `fs-source`: read bcast data from the TPV bcast directory, `file://` URLs
+//! * `serve`: serve the bcast data to other instances and push it to other tools
+//!
+//! Replaying recorded sessions (`replay://`) and the simulator (`sim://`) are
+//! always available, so is recording.
+
 #![warn(clippy::all, rust_2018_idioms)]
 
+#[cfg(feature = "gui")]
 mod app;
+#[cfg(feature = "gui")]
 pub use app::TpvUiApp;
 
-mod data;
\ No newline at end of file
+mod headless;
+pub use headless::Headless;
+
+pub mod data;
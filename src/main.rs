@@ -1,13 +1,67 @@
 #![warn(clippy::all, rust_2018_idioms)]
-#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+#![cfg_attr(all(not(debug_assertions), feature = "gui"), windows_subsystem = "windows")] // hide console window on Windows in release
 
-fn main() -> eframe::Result {
+fn main() {
     // env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    #[cfg(feature = "gui")]
     if cfg!(debug_assertions) {
         colog::init();
     }
     log::info!("tpvui");
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // without the app there is only the headless mode
+    if !cfg!(feature = "gui") || args.iter().any(|a| a == "--headless") {
+        #[cfg(all(windows, not(debug_assertions), feature = "gui"))]
+        attach_console();
+
+        std::process::exit(headless(&args));
+    }
+
+    #[cfg(feature = "gui")]
+    if let Err(e) = gui() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+// release builds with the app have no console on Windows, use the one of the
+// shell started from, so the headless output is not lost
+#[cfg(all(windows, not(debug_assertions), feature = "gui"))]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // fails if started without a console or if the output is redirected already, both is fine
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+fn headless(args: &[String]) -> i32 {
+    match tpvui::Headless::from_args(args) {
+        Ok(Some(headless)) => match headless.run() {
+            Ok(_) => 0,
+            Err(e) => {
+                eprintln!("{}", e);
+                1
+            }
+        },
+        Ok(None) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+fn gui() -> eframe::Result {
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])